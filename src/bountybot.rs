@@ -1,10 +1,11 @@
+use crate::RepoIssue;
 use eyre::Result;
 use owo_colors::OwoColorize;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Issue {
-    number: i32,
+    number: u64,
     title: String,
 }
 
@@ -27,15 +28,31 @@ struct GraphQLResponse {
 }
 
 /// A quest issue that can be selected by the user
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct QuestIssue {
     pub title: String,
-    pub repo_ref: String,
+    pub issue: RepoIssue,
+    /// Name of the quest source this issue was discovered through
+    pub source: String,
+}
+
+impl QuestIssue {
+    /// The `owner/repo#123` reference for this quest
+    #[must_use]
+    pub fn repo_ref(&self) -> String {
+        self.issue.to_string()
+    }
 }
 
 impl std::fmt::Display for QuestIssue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.title.bold(), self.repo_ref.dimmed(),)
+        write!(
+            f,
+            "{} {} {}",
+            self.title.bold(),
+            self.repo_ref().dimmed(),
+            format!("[{}]", self.source).dimmed()
+        )
     }
 }
 
@@ -89,11 +106,24 @@ impl BountyBotClient {
             .into_iter()
             .map(|quest| QuestIssue {
                 title: quest.issue.title,
-                repo_ref: format!(
-                    "{}/{}#{}",
-                    quest.organization, quest.repository, quest.issue.number
-                ),
+                issue: RepoIssue {
+                    owner: quest.organization,
+                    repo: quest.repository,
+                    issue_number: quest.issue.number,
+                },
+                source: "BountyBot".to_string(),
             })
             .collect())
     }
 }
+
+#[async_trait::async_trait]
+impl crate::sources::QuestSource for BountyBotClient {
+    fn name(&self) -> String {
+        "BountyBot".to_string()
+    }
+
+    async fn fetch_quests(&self) -> Result<Vec<QuestIssue>> {
+        self.fetch_active_quests().await
+    }
+}
@@ -0,0 +1,214 @@
+use eyre::{bail, Result};
+use git2::{BranchType, Oid, Repository};
+use std::collections::BTreeMap;
+
+/// Give up looking for a free branch name after this many suffixes
+const MAX_BRANCH_SUFFIX: u32 = 100;
+
+/// The branch to work on, chosen so existing work is never overwritten
+#[derive(Debug, PartialEq, Eq)]
+pub struct BranchPlan {
+    pub name: String,
+    /// Commit the local branch should point at
+    pub tip: Oid,
+    /// Whether the branch already has commits on top of the base
+    pub has_work: bool,
+    /// What was found under the wanted name, to tell the user
+    pub note: Option<String>,
+}
+
+/// What already exists under a branch name, locally and on the fork
+enum Existing {
+    Free,
+    Reusable { tip: Oid, ahead: usize },
+    Taken(&'static str),
+}
+
+/// Pick the branch for `name`: reuse it if it only has our commits, otherwise the first free
+/// `name-2`, `name-3`, ...
+///
+/// `remote_heads` are the fork's branches, and `fetch` fetches one of them, returning its tip.
+pub fn plan(
+    git_repo: &Repository,
+    base: Oid,
+    name: &str,
+    remote_heads: &BTreeMap<String, Oid>,
+    my_emails: &[String],
+    fetch: &mut dyn FnMut(&str) -> Result<Oid>,
+) -> Result<BranchPlan> {
+    let mut taken = None;
+    let candidates = std::iter::once(name.to_string())
+        .chain((2..=MAX_BRANCH_SUFFIX).map(|suffix| format!("{name}-{suffix}")));
+
+    for candidate in candidates {
+        let existing = inspect(git_repo, base, &candidate, remote_heads, my_emails, fetch)?;
+        let (tip, ahead) = match existing {
+            Existing::Free => (base, 0),
+            Existing::Reusable { tip, ahead } => (tip, ahead),
+            Existing::Taken(reason) => {
+                taken.get_or_insert(reason);
+                continue;
+            }
+        };
+
+        let note = match (taken, ahead) {
+            (Some(reason), _) => Some(format!("branch {name} {reason}, using {candidate}")),
+            (None, 0) => None,
+            (None, ahead) => Some(format!(
+                "reusing branch {name}, which has {ahead} of your commits"
+            )),
+        };
+        return Ok(BranchPlan {
+            name: candidate,
+            tip,
+            has_work: ahead > 0,
+            note,
+        });
+    }
+
+    bail!("no free branch name found for {name}")
+}
+
+fn inspect(
+    git_repo: &Repository,
+    base: Oid,
+    name: &str,
+    remote_heads: &BTreeMap<String, Oid>,
+    my_emails: &[String],
+    fetch: &mut dyn FnMut(&str) -> Result<Oid>,
+) -> Result<Existing> {
+    let local = git_repo
+        .find_branch(name, BranchType::Local)
+        .ok()
+        .and_then(|branch| branch.get().target());
+    let remote = if remote_heads.contains_key(name) {
+        Some(fetch(name)?)
+    } else {
+        None
+    };
+
+    let tip = match (local, remote) {
+        (None, None) => return Ok(Existing::Free),
+        // Local branches are the user's own work
+        (Some(local), None) => local,
+        (None, Some(remote)) => remote,
+        (Some(local), Some(remote)) => {
+            let Some(tip) = newest(git_repo, local, remote) else {
+                return Ok(Existing::Taken("has diverged from your fork"));
+            };
+            tip
+        }
+    };
+
+    if let Some(remote) = remote {
+        if !only_mine(git_repo, remote, base, my_emails) {
+            return Ok(Existing::Taken("has commits on your fork you didn't make"));
+        }
+    }
+
+    let ahead = commits_since(git_repo, tip, base).map_or(0, |commits| commits.len());
+    Ok(Existing::Reusable { tip, ahead })
+}
+
+/// Whichever of two commits contains the other, or `None` if they've diverged
+fn newest(git_repo: &Repository, one: Oid, other: Oid) -> Option<Oid> {
+    if one == other || git_repo.graph_descendant_of(one, other).ok()? {
+        return Some(one);
+    }
+    git_repo
+        .graph_descendant_of(other, one)
+        .ok()?
+        .then_some(other)
+}
+
+/// Whether every commit on `tip` since `base` was authored with one of `my_emails`
+///
+/// History that can't be walked (e.g. beyond a shallow clone) counts as someone else's.
+fn only_mine(git_repo: &Repository, tip: Oid, base: Oid, my_emails: &[String]) -> bool {
+    let is_mine = |oid: &Oid| {
+        git_repo
+            .find_commit(*oid)
+            .is_ok_and(|commit| authored_by(&commit, my_emails))
+    };
+    commits_since(git_repo, tip, base).is_ok_and(|commits| commits.iter().all(is_mine))
+}
+
+fn authored_by(commit: &git2::Commit, emails: &[String]) -> bool {
+    let author = commit.author();
+    let Some(email) = author.email() else {
+        return false;
+    };
+    emails.iter().any(|mine| mine.eq_ignore_ascii_case(email))
+}
+
+fn commits_since(git_repo: &Repository, tip: Oid, base: Oid) -> Result<Vec<Oid>> {
+    let mut walk = git_repo.revwalk()?;
+    walk.push(tip)?;
+    walk.hide(base)?;
+    Ok(walk.collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+
+    const ME: &str = "me@example.com";
+
+    fn commit(git_repo: &Repository, email: &str, parents: &[Oid]) -> Oid {
+        let signature = Signature::now("Someone", email).unwrap();
+        let tree = git_repo
+            .find_tree(git_repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parents: Vec<_> = parents
+            .iter()
+            .map(|oid| git_repo.find_commit(*oid).unwrap())
+            .collect();
+        let parents: Vec<_> = parents.iter().collect();
+        git_repo
+            .commit(None, &signature, &signature, email, &tree, &parents)
+            .unwrap()
+    }
+
+    fn plan_with(git_repo: &Repository, base: Oid, heads: &[(&str, Oid)]) -> BranchPlan {
+        let remote_heads: BTreeMap<_, _> = heads
+            .iter()
+            .map(|(name, oid)| ((*name).to_string(), *oid))
+            .collect();
+        let mut fetch = |name: &str| Ok(remote_heads[name]);
+        plan(
+            git_repo,
+            base,
+            "issue-1",
+            &remote_heads,
+            &[ME.to_string()],
+            &mut fetch,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan_branch_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let git_repo = Repository::init(dir.path()).unwrap();
+        let base = commit(&git_repo, "upstream@example.com", &[]);
+        let mine = commit(&git_repo, ME, &[base]);
+        let theirs = commit(&git_repo, "them@example.com", &[base]);
+
+        let fresh = plan_with(&git_repo, base, &[]);
+        assert_eq!((fresh.name.as_str(), fresh.tip), ("issue-1", base));
+        assert!(!fresh.has_work && fresh.note.is_none());
+
+        let reused = plan_with(&git_repo, base, &[("issue-1", mine)]);
+        assert_eq!((reused.name.as_str(), reused.tip), ("issue-1", mine));
+        assert!(reused.has_work);
+
+        let renamed = plan_with(
+            &git_repo,
+            base,
+            &[("issue-1", theirs), ("issue-1-2", theirs)],
+        );
+        assert_eq!((renamed.name.as_str(), renamed.tip), ("issue-1-3", base));
+        assert!(renamed.note.unwrap().contains("using issue-1-3"));
+    }
+}
@@ -1,12 +1,26 @@
 use crate::{GitHub, CLIENT_ID};
-use inquire::Confirm;
 use owo_colors::OwoColorize;
 use tracing::debug;
 
+mod abandon;
+mod complete;
 mod completion;
+mod config;
+mod debug;
+mod finish;
+mod list;
 mod login;
+mod path;
+mod pr;
+mod prompt;
+mod setup;
+mod shell_init;
 mod solve;
 mod start;
+mod telemetry;
+mod watch;
+
+pub use solve::SolveArgs;
 
 fn print_step(text: &str) {
     println!("\n{}", text.bold().bright_blue());
@@ -16,6 +30,14 @@ fn print_success(text: &str) {
     println!("\n{}", text.bright_green());
 }
 
+fn print_info(text: &str) {
+    eprintln!("{} {text}", "ℹ".bright_blue());
+}
+
+fn print_warning(text: &str) {
+    eprintln!("{} {text}", "⚠".yellow());
+}
+
 #[derive(clap::Subcommand, Debug)]
 #[command(about = "A CLI tool for managing GitHub bounties")]
 pub enum Command {
@@ -27,29 +49,32 @@ pub enum Command {
     /// 2. Create a new branch
     /// 3. Set up a draft PR
     #[command(name = "solve", aliases = ["s"], display_order = 1)]
-    #[allow(clippy::doc_markdown)]
-    Solve {
-        /// Issue reference in any of these formats:
-        ///
-        /// - https://github.com/owner/repo/issues/123
-        ///
-        /// - github.com/owner/repo/issues/123
-        ///
-        /// - owner/repo/issues/123
-        ///
-        /// - owner/repo/123
-        ///
-        /// - owner/repo#123
-        #[arg(required = false)]
+    Solve(SolveArgs),
+
+    /// 📝 Open the draft pull request for a bounty started with `--no-pr`
+    ///
+    /// Reuses an existing pull request for the branch if there is one.
+    #[command(name = "pr", display_order = 5)]
+    Pr {
+        /// The bounty's issue reference (defaults to the checkout you're in)
         issue_ref: Option<String>,
+
+        /// Wait for the branch to reach the fork first (used by the pre-push hook, with the
+        /// local refs being pushed)
+        #[arg(long, hide = true, num_args = 0.., value_name = "REF")]
+        after_push: Option<Vec<String>>,
     },
 
     /// 🔧 Generate shell completion scripts
     #[command(name = "completion", aliases = ["c"], display_order = 3)]
     Completion {
-        /// The shell to generate completions for
+        /// The shell to generate completions for (defaults to the one in $SHELL)
         #[arg(value_enum)]
-        shell: clap_complete::Shell,
+        shell: Option<clap_complete::Shell>,
+
+        /// Install the completions where your shell loads them from, instead of printing them
+        #[arg(long)]
+        install: bool,
     },
 
     /// 🔑 Login to GitHub using device flow
@@ -59,12 +84,146 @@ pub enum Command {
         #[arg(long, env = "GITHUB_CLIENT_ID", default_value = CLIENT_ID)]
         client_id: String,
     },
+
+    /// 🏳 Stop working on a bounty
+    ///
+    /// Deletes your claim comment and unassigns you; pass `--close-pr` to also close the
+    /// draft PR.
+    #[command(name = "abandon", display_order = 6)]
+    Abandon {
+        /// The issue reference you used with `bounty solve`, e.g. owner/repo#123
+        issue_ref: String,
+
+        /// Close the bounty's pull request too
+        #[arg(long)]
+        close_pr: bool,
+    },
+
+    /// 🏁 Stop tracking a bounty you're done with
+    ///
+    /// Removes its worktree, if it has one, and leaves the claim and pull request alone.
+    #[command(name = "finish", display_order = 7)]
+    Finish {
+        /// The bounty's issue reference (defaults to the checkout you're in)
+        issue_ref: Option<String>,
+    },
+
+    /// 📋 List the bounties you're working on, refreshing their pull request status
+    #[command(name = "list", aliases = ["ls"], display_order = 8)]
+    List,
+
+    /// 🐚 Print shell integration to eval, adding `bounty cd <issue-ref>`
+    ///
+    /// For example, add `eval "$(bounty shell-init bash)"` to your ~/.bashrc, or
+    /// `bounty shell-init fish | source` to your fish config.
+    #[command(name = "shell-init", display_order = 9)]
+    ShellInit {
+        /// The shell to integrate with
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// 📂 Print a bounty's checkout directory (what `bounty cd` changes to)
+    #[command(name = "path", display_order = 10)]
+    Path {
+        /// owner/repo#123, or just the issue number (defaults to the checkout you're in)
+        issue_ref: Option<String>,
+    },
+
+    /// 💬 Print the bounty in the current directory for your prompt, without network access
+    ///
+    /// Shows e.g. `acme/rocket#12 draft ✓`; the status is refreshed by `bounty list`.
+    #[command(name = "prompt", display_order = 11)]
+    Prompt,
+
+    /// ⚙️ Walk through the main settings again: workspace, git, pull requests, quests and more
+    #[command(name = "setup", display_order = 12)]
+    Setup,
+
+    /// 🛠 Read and change settings
+    ///
+    /// Settings come from the defaults, /etc/bounty/config.toml, your config file and
+    /// `BOUNTY_*` environment variables (e.g. `BOUNTY_SOLVE__PR=manual`), each overriding the
+    /// ones before.
+    #[command(name = "config", display_order = 13)]
+    Config {
+        #[command(subcommand)]
+        action: config::ConfigAction,
+    },
+
+    /// 🛡 Choose whether crash reports are sent, and see what they contain
+    #[command(name = "telemetry", display_order = 14)]
+    Telemetry {
+        #[command(subcommand)]
+        action: telemetry::TelemetryAction,
+    },
+
+    /// 🐞 Gather what's needed for a bug report
+    #[command(name = "debug", display_order = 15)]
+    Debug {
+        #[command(subcommand)]
+        action: debug::DebugAction,
+    },
+
+    /// Completion candidates for shell scripts
+    #[command(name = "dynamic-complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: complete::CompletionKind,
+
+        /// What has been typed so far
+        #[arg(default_value = "")]
+        prefix: String,
+
+        /// Add a tab-separated description to each candidate
+        #[arg(long)]
+        describe: bool,
+    },
+
+    /// 👀 Watch for new quests and get notified when they match your filters
+    ///
+    /// Polls your quest sources (BountyBot, GitHub bounty labels and any
+    /// configured feeds) and notifies you about quests that match the filters
+    /// in your config.
+    #[command(name = "watch", aliases = ["w"], display_order = 4)]
+    Watch {
+        /// Seconds between polls (defaults to `watch.interval_secs` in the config)
+        #[arg(long)]
+        interval: Option<u64>,
+
+        /// Search GitHub for open issues with this label instead of `sources.github_labels` (can be repeated)
+        #[arg(long = "label")]
+        labels: Vec<String>,
+
+        /// Poll a single time and exit
+        #[arg(long)]
+        once: bool,
+    },
 }
 
 impl Command {
+    /// Whether a run gets a log file; not for commands run by shells on every prompt or
+    /// keypress, which would push out the logs worth keeping
+    #[must_use]
+    pub const fn keeps_log(&self) -> bool {
+        !matches!(
+            self,
+            Self::Complete { .. } | Self::Prompt | Self::Path { .. } | Self::ShellInit { .. }
+        )
+    }
+
     /// Returns true if this command requires authentication
     const fn requires_auth(&self) -> bool {
-        matches!(self, Self::Solve { .. })
+        matches!(
+            self,
+            Self::Solve(_) | Self::Pr { .. } | Self::Abandon { .. } | Self::List
+        )
+    }
+
+    /// Returns true if this command uses the GitHub token from the config; the others load
+    /// the config themselves if they need it, so `bounty config` can fix a broken one
+    const fn uses_token(&self) -> bool {
+        self.requires_auth() || matches!(self, Self::Watch { .. })
     }
 }
 
@@ -72,36 +231,26 @@ impl Command {
 ///
 /// # Arguments
 /// * `command` - The command to execute
-/// * `github_token` - Optional GitHub token for authentication
 ///
 /// # Returns
 /// * `eyre::Result<()>` - Result of the command execution
 ///
-/// # Panics
-/// * When unwrapping the GitHub token in the Start command, which is safe because we check for token presence before execution
-pub async fn handle(command: Command, github_token: Option<&str>) -> eyre::Result<()> {
+pub async fn handle(command: Command) -> eyre::Result<()> {
     debug!(?command, "handling bounty command");
 
-    // Convert input token to owned string if present
-    let mut token = github_token.map(String::from);
+    let mut token = if command.uses_token() {
+        crate::config::Config::load()?.try_get_github_token()
+    } else {
+        None
+    };
 
     // If command requires auth and we don't have a token, trigger login flow
     if command.requires_auth() && token.is_none() {
-        println!("This command requires authentication.\n");
+        eprintln!("This command requires authentication.\n");
         token = Some(crate::github::login(CLIENT_ID).await?);
     }
 
-    match command {
-        Command::Solve { issue_ref } => {
-            // We can safely unwrap here because we either have a token or would have returned above
-            let github_token = token.unwrap();
-            let github = GitHub::new(&github_token)?;
-            solve::handle(issue_ref, github).await?;
-        }
-        Command::Completion { shell } => completion::handle(shell)?,
-        Command::Login { client_id } => login::handle(&client_id).await?,
-    }
-    Ok(())
+    command.handle_command(token.as_deref()).await
 }
 
 /// Handle first-time setup and default command selection
@@ -112,21 +261,7 @@ pub async fn handle_default_command() -> eyre::Result<()> {
         crate::animation::show_welcome_animation();
         print_step("Welcome to BountyBot CLI");
 
-        // Ask about shell completion
-        if Confirm::new("Set up fish shell completions?")
-            .with_default(true)
-            .prompt()?
-        {
-            completion::handle(clap_complete::Shell::Fish)?;
-            print_success("Fish completions installed");
-        }
-
-        // Always trigger login on first run
-        print_step("Setting up GitHub access");
-        let token = crate::github::login(CLIENT_ID).await?;
-        config.set_github_token(token)?;
-
-        config.complete_first_time_setup()?;
+        setup::run(&mut config).await?;
         print_success("Setup complete - ready to work on bounties!");
     }
 
@@ -140,7 +275,7 @@ pub async fn handle_default_command() -> eyre::Result<()> {
         .await?;
     } else {
         // We have a token, so show the start command by default
-        Command::Solve { issue_ref: None }
+        Command::Solve(SolveArgs::default())
             .handle_command(config.try_get_github_token().as_deref())
             .await?;
     }
@@ -152,13 +287,46 @@ impl Command {
     /// Handle a single command
     async fn handle_command(self, token: Option<&str>) -> eyre::Result<()> {
         match self {
-            Self::Solve { issue_ref } => {
+            Self::Solve(args) => {
                 // We can safely unwrap here because we either have a token or would have returned above
                 let github = GitHub::new(token.unwrap())?;
-                solve::handle(issue_ref, github).await?;
+                solve::handle(args, github).await?;
             }
-            Self::Completion { shell } => completion::handle(shell)?,
+            Self::Pr {
+                issue_ref,
+                after_push,
+            } => {
+                let github = GitHub::new(token.unwrap())?;
+                pr::handle(issue_ref, after_push, github).await?;
+            }
+            Self::Completion { shell, install } => completion::handle(shell, install)?,
             Self::Login { client_id } => login::handle(&client_id).await?,
+            Self::Abandon {
+                issue_ref,
+                close_pr,
+            } => {
+                let github = GitHub::new(token.unwrap())?;
+                abandon::handle(&issue_ref, close_pr, github).await?;
+            }
+            Self::Finish { issue_ref } => finish::handle(issue_ref.as_deref())?,
+            Self::List => list::handle(GitHub::new(token.unwrap())?).await?,
+            Self::ShellInit { shell } => shell_init::handle(shell)?,
+            Self::Path { issue_ref } => path::handle(issue_ref.as_deref())?,
+            Self::Prompt => prompt::handle()?,
+            Self::Setup => setup::handle().await?,
+            Self::Config { action } => config::handle(action)?,
+            Self::Telemetry { action } => telemetry::handle(action)?,
+            Self::Debug { action } => debug::handle(action)?,
+            Self::Complete {
+                kind,
+                prefix,
+                describe,
+            } => complete::handle(kind, &prefix, describe).await?,
+            Self::Watch {
+                interval,
+                labels,
+                once,
+            } => watch::handle(interval, labels, once, token).await?,
         }
         Ok(())
     }
@@ -0,0 +1,58 @@
+use crate::state::{State, TrackedBounty};
+use crate::{GitHub, RepoIssue};
+use eyre::Result;
+
+pub async fn handle(issue_ref: &str, close_pr: bool, github: GitHub) -> Result<()> {
+    let issue = RepoIssue::parse(issue_ref)?;
+    let mut state = State::load()?;
+    let Some(bounty) = state.find(&issue).cloned() else {
+        eyre::bail!("not working on {issue}");
+    };
+
+    // First, so uncommitted work stops us before anything is undone on GitHub
+    remove_worktree(&bounty)?;
+
+    let RepoIssue {
+        owner,
+        repo,
+        issue_number,
+    } = &issue;
+
+    if let Some(comment_id) = bounty.claim_comment_id {
+        github.delete_comment(owner, repo, comment_id).await?;
+        println!("🗑  Deleted claim comment");
+    }
+
+    if bounty.self_assigned {
+        let user = github.current_user().await?.login;
+        github.unassign(owner, repo, *issue_number, &user).await?;
+        println!("👋 Unassigned you from the issue");
+    }
+
+    if let Some(pr_number) = bounty.pr_number.filter(|_| close_pr) {
+        if github.close_pr(owner, repo, pr_number).await? {
+            println!("📕 Closed pull request #{pr_number}");
+        }
+    } else if let Some(pr_number) = bounty.pr_number {
+        super::print_info(&format!(
+            "Left pull request #{pr_number} open; pass --close-pr to close it"
+        ));
+    }
+
+    state.remove(&issue);
+    state.save()?;
+    super::print_success(&format!("Abandoned {issue}"));
+
+    Ok(())
+}
+
+/// Remove the bounty's checkout if it's a worktree of a shared clone
+pub(super) fn remove_worktree(bounty: &TrackedBounty) -> Result<()> {
+    let Some(checkout) = &bounty.checkout else {
+        return Ok(());
+    };
+    if crate::git::remove_worktree(checkout)? {
+        println!("🧹 Removed worktree {}", checkout.display());
+    }
+    Ok(())
+}
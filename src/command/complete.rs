@@ -0,0 +1,94 @@
+use crate::quest_cache::QuestCache;
+use crate::sources::{configured_sources, fetch_all};
+use crate::state::State;
+use crate::{Config, GitHub};
+use eyre::Result;
+use std::collections::BTreeSet;
+use std::process::{Command, Stdio};
+
+/// What the hidden `dynamic-complete` command completes
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionKind {
+    /// Issue references of tracked bounties and cached quests
+    IssueRef,
+    /// Refresh the quest cache (started in the background when it's stale)
+    RefreshQuests,
+}
+
+pub async fn handle(kind: CompletionKind, prefix: &str, describe: bool) -> Result<()> {
+    match kind {
+        CompletionKind::IssueRef => {
+            print_issue_refs(prefix, describe);
+            Ok(())
+        }
+        CompletionKind::RefreshQuests => refresh_quests().await,
+    }
+}
+
+fn print_issue_refs(prefix: &str, describe: bool) {
+    for (issue_ref, description) in issue_refs(prefix) {
+        if describe {
+            println!("{issue_ref}\t{description}");
+        } else {
+            println!("{issue_ref}");
+        }
+    }
+}
+
+/// Tracked bounties first, then cached quests, matching `prefix` case-insensitively
+///
+/// Never touches the network; a stale quest cache is refreshed in the background for next time.
+fn issue_refs(prefix: &str) -> Vec<(String, String)> {
+    let tracked = State::load()
+        .map(|state| state.bounties)
+        .unwrap_or_default();
+    let mut cache = QuestCache::load().unwrap_or_default();
+    if cache.is_stale() {
+        refresh_in_background(&mut cache);
+    }
+
+    let prefix = prefix.to_lowercase();
+    let mut seen = BTreeSet::new();
+    tracked
+        .iter()
+        .map(|bounty| (bounty.repo_issue().to_string(), "working on it".to_string()))
+        .chain(
+            cache
+                .quests
+                .into_iter()
+                .map(|quest| (quest.issue_ref, quest.title)),
+        )
+        .filter(|(issue_ref, _)| issue_ref.to_lowercase().starts_with(&prefix))
+        .filter(|(issue_ref, _)| seen.insert(issue_ref.clone()))
+        .collect()
+}
+
+fn refresh_in_background(cache: &mut QuestCache) {
+    cache.touch();
+    if cache.save().is_err() {
+        return;
+    }
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let _ = Command::new(exe)
+        .args(["dynamic-complete", "refresh-quests"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+async fn refresh_quests() -> Result<()> {
+    let config = Config::load()?;
+    let github = config
+        .try_get_github_token()
+        .as_deref()
+        .map(GitHub::new)
+        .transpose()?;
+    let fetched = fetch_all(&configured_sources(&config.sources, github)).await;
+    if !fetched.all_failed() {
+        crate::quest_cache::store(&fetched.quests);
+    }
+    Ok(())
+}
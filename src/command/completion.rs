@@ -1,34 +1,224 @@
+use crate::config::xdg_dir;
 use crate::Cli;
-use clap::{CommandFactory, ValueEnum};
-use clap_complete::Generator;
-use eyre::Result;
-use owo_colors::OwoColorize;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use eyre::{bail, eyre, Result, WrapErr};
+use inquire::Confirm;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn handle(shell: impl ValueEnum + Generator) -> Result<()> {
+/// Subcommands (and aliases) whose argument is an issue reference
+const ISSUE_REF_COMMANDS: &[&str] = &["solve", "s", "pr", "abandon", "finish", "path"];
+
+/// Marks the line `--install` adds to a shell's startup file
+const RC_MARKER: &str = "# bounty completions";
+
+pub fn handle(shell: Option<Shell>, install: bool) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => detect_shell()?,
+    };
+
+    if !install {
+        print!("{}", script(shell)?);
+        return Ok(());
+    }
+
+    let (path, rc_file) = install_completions(shell)?;
+    super::print_success(&format!(
+        "Installed {shell} completions to {}",
+        path.display()
+    ));
+    if let Some(rc_file) = rc_file {
+        super::print_info(&format!("Loading them from {}", rc_file.display()));
+    }
+    super::print_info("Restart your shell to use them");
+    Ok(())
+}
+
+/// Ask whether to install completions for the user's shell, if it can be detected
+pub fn offer_install() -> Result<()> {
+    let Ok(shell) = detect_shell() else {
+        return Ok(());
+    };
+    if Confirm::new(&format!("Set up {shell} completions?"))
+        .with_default(true)
+        .prompt()?
+    {
+        handle(Some(shell), true)?;
+    }
+    Ok(())
+}
+
+/// The shell named by `$SHELL`
+pub fn detect_shell() -> Result<Shell> {
+    Shell::from_env()
+        .ok_or_else(|| eyre!("couldn't detect your shell from $SHELL, pass it explicitly"))
+}
+
+/// The static clap completions plus the dynamic glue for `shell`
+fn script(shell: Shell) -> Result<String> {
     let mut cmd = Cli::command();
     let name = cmd.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut cmd, name, &mut script);
+    let mut script = String::from_utf8(script).wrap_err("completions aren't valid UTF-8")?;
+    script.push_str(&dynamic_completions(&shell.to_string()));
+    Ok(script)
+}
 
-    if shell.to_possible_value().unwrap().get_name() == "fish" {
-        // Create fish completions directory if it doesn't exist
-        let fish_dir = dirs::home_dir()
-            .ok_or_else(|| eyre::eyre!("Could not determine home directory"))?
-            .join(".config")
-            .join("fish")
-            .join("completions");
+/// Write the completions where `shell` looks for them, returning their path and the startup
+/// file that loads them, if one had to be used
+///
+/// Safe to run again: the script is overwritten and the startup file only edited once.
+pub fn install_completions(shell: Shell) -> Result<(PathBuf, Option<PathBuf>)> {
+    let home = dirs::home_dir().ok_or_else(|| eyre!("could not determine home directory"))?;
+    let path = completions_path(shell, &home)?;
+    let dir = path.parent().expect("completions path has a parent");
+    fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    fs::write(&path, script(shell)?)
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
 
-        fs::create_dir_all(&fish_dir)?;
+    let Some((rc_file, line)) = rc_line(shell, &home, &path) else {
+        return Ok((path, None));
+    };
+    add_line(&rc_file, &line)?;
+    Ok((path, Some(rc_file)))
+}
 
-        let completion_path = fish_dir.join(format!("{name}.fish"));
-        let mut file = fs::File::create(&completion_path)?;
+/// Where completions for `shell` go: the directories bash-completion and fish load from, or a
+/// file of our own for shells that need it sourced
+fn completions_path(shell: Shell, home: &Path) -> Result<PathBuf> {
+    let data_home = xdg_dir("XDG_DATA_HOME", home, ".local/share");
+    let path = match shell {
+        Shell::Bash => data_home.join("bash-completion/completions/bounty"),
+        Shell::Fish => {
+            xdg_dir("XDG_CONFIG_HOME", home, ".config").join("fish/completions/bounty.fish")
+        }
+        Shell::Zsh => data_home.join("bounty/completions/bounty.zsh"),
+        Shell::Elvish => data_home.join("bounty/completions/bounty.elv"),
+        Shell::PowerShell => data_home.join("bounty/completions/bounty.ps1"),
+        _ => bail!("installing {shell} completions isn't supported"),
+    };
+    Ok(path)
+}
 
-        clap_complete::generate(shell, &mut cmd, name, &mut file);
+/// The startup file and line that load `path`, for shells without a completions directory
+fn rc_line(shell: Shell, home: &Path, path: &Path) -> Option<(PathBuf, String)> {
+    let path = path.display();
+    match shell {
+        // Sourced after compinit has run, rather than autoloaded from $fpath, so the dynamic
+        // completions can register themselves with compdef
+        Shell::Zsh => {
+            let zdotdir =
+                std::env::var_os("ZDOTDIR").map_or_else(|| home.to_path_buf(), PathBuf::from);
+            Some((zdotdir.join(".zshrc"), format!("source \"{path}\"")))
+        }
+        Shell::Elvish => Some((
+            xdg_dir("XDG_CONFIG_HOME", home, ".config").join("elvish/rc.elv"),
+            format!("eval (slurp < \"{path}\")"),
+        )),
+        Shell::PowerShell => Some((powershell_profile(home), format!(". \"{path}\""))),
+        _ => None,
+    }
+}
 
-        println!("  {}", completion_path.display().to_string().dimmed());
+/// `$PROFILE` for the current user and PowerShell 7
+fn powershell_profile(home: &Path) -> PathBuf {
+    let dir = if cfg!(windows) {
+        dirs::document_dir()
+            .unwrap_or_else(|| home.join("Documents"))
+            .join("PowerShell")
     } else {
-        // For other shells, just print to stdout
-        clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        xdg_dir("XDG_CONFIG_HOME", home, ".config").join("powershell")
+    };
+    dir.join("Microsoft.PowerShell_profile.ps1")
+}
+
+/// Append `line` to `rc_file` unless it's already there, returning whether it was added
+fn add_line(rc_file: &Path, line: &str) -> Result<bool> {
+    let contents = match fs::read_to_string(rc_file) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).wrap_err_with(|| format!("failed to read {}", rc_file.display()))
+        }
+    };
+    if contents.lines().any(|existing| existing.trim() == line) {
+        return Ok(false);
     }
 
-    Ok(())
+    let separator = if contents.is_empty() || contents.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    if let Some(dir) = rc_file.parent() {
+        fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    }
+    fs::write(
+        rc_file,
+        format!("{contents}{separator}\n{RC_MARKER}\n{line}\n"),
+    )
+    .wrap_err_with(|| format!("failed to write {}", rc_file.display()))?;
+    Ok(true)
+}
+
+/// Glue completing issue references through the hidden `dynamic-complete` command, which the
+/// static clap completions can't do
+fn dynamic_completions(shell: &str) -> String {
+    let commands = ISSUE_REF_COMMANDS.join(" ");
+    match shell {
+        "fish" => format!(
+            r#"
+complete -c bounty -n "__fish_seen_subcommand_from {commands}" -f -a "(bounty dynamic-complete issue-ref (commandline -ct) --describe)"
+"#
+        ),
+        "bash" => format!(
+            r#"
+_bounty_issue_refs() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    if [[ $COMP_CWORD -gt 1 && " {commands} " == *" ${{COMP_WORDS[1]}} "* && "$cur" != -* ]]; then
+        COMPREPLY=($(compgen -W "$(bounty dynamic-complete issue-ref "$cur" 2>/dev/null)" -- "$cur"))
+        [[ ${{#COMPREPLY[@]}} -gt 0 ]] && return 0
+    fi
+    _bounty "$@"
+}}
+complete -F _bounty_issue_refs -o nosort -o bashdefault -o default bounty
+"#
+        ),
+        "zsh" => format!(
+            r#"
+_bounty_issue_refs() {{
+    if (( CURRENT > 2 )) && [[ " {commands} " == *" ${{words[2]}} "* && ${{words[CURRENT]}} != -* ]]; then
+        local -a refs
+        refs=(${{(f)"$(bounty dynamic-complete issue-ref "${{words[CURRENT]}}" 2>/dev/null)"}})
+        (( ${{#refs}} )) && compadd -a refs && return 0
+    fi
+    _bounty "$@"
+}}
+compdef _bounty_issue_refs bounty
+"#
+        ),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_line_is_idempotent() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc_file = dir.path().join(".zshrc");
+        fs::write(&rc_file, "autoload -Uz compinit && compinit").unwrap();
+
+        assert!(add_line(&rc_file, "source \"/tmp/bounty.zsh\"").unwrap());
+        assert!(!add_line(&rc_file, "source \"/tmp/bounty.zsh\"").unwrap());
+        assert_eq!(
+            fs::read_to_string(&rc_file).unwrap(),
+            "autoload -Uz compinit && compinit\n\n# bounty completions\nsource \"/tmp/bounty.zsh\"\n"
+        );
+    }
 }
@@ -0,0 +1,296 @@
+use crate::config::layers::{self, Entry, Key, Layers};
+use crate::config::{config_path, Config};
+use eyre::{bail, Result, WrapErr};
+use inquire::Confirm;
+use owo_colors::OwoColorize;
+use std::path::Path;
+use std::process::Command;
+
+/// Shown instead of tokens and other secrets
+const REDACTED: &str = "********";
+
+#[derive(clap::Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting, e.g. `solve.pr`, or every setting in a table, e.g. `solve`
+    Get {
+        key: String,
+
+        /// Show where each value comes from
+        #[arg(long)]
+        show_origin: bool,
+    },
+
+    /// Change a setting in your config file, e.g. `bounty config set solve.pr manual`
+    Set { key: String, value: String },
+
+    /// Remove a setting from your config file, going back to the default
+    Unset { key: String },
+
+    /// List every setting
+    List {
+        /// Show where each value comes from
+        #[arg(long)]
+        show_origin: bool,
+    },
+
+    /// Open your config file in your editor, checking it when you're done
+    Edit,
+
+    /// Print the path of your config file
+    Path,
+}
+
+// Commands are logged, and the log files shouldn't hold tokens
+impl std::fmt::Debug for ConfigAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Get { key, show_origin } => f
+                .debug_struct("Get")
+                .field("key", key)
+                .field("show_origin", show_origin)
+                .finish(),
+            Self::Set { key, value } => f
+                .debug_struct("Set")
+                .field("key", key)
+                .field("value", &redacted(key, value))
+                .finish(),
+            Self::Unset { key } => f.debug_struct("Unset").field("key", key).finish(),
+            Self::List { show_origin } => f
+                .debug_struct("List")
+                .field("show_origin", show_origin)
+                .finish(),
+            Self::Edit => f.write_str("Edit"),
+            Self::Path => f.write_str("Path"),
+        }
+    }
+}
+
+/// `value`, unless `key` may be a secret
+fn redacted<'a>(key: &str, value: &'a str) -> &'a str {
+    let secret = layers::parse_key(key).map_or(true, |key| is_secret(&key));
+    if secret {
+        REDACTED
+    } else {
+        value
+    }
+}
+
+pub fn handle(action: ConfigAction) -> Result<()> {
+    match action {
+        ConfigAction::Get { key, show_origin } => get(&key, show_origin),
+        ConfigAction::Set { key, value } => set(&key, &value),
+        ConfigAction::Unset { key } => unset(&key),
+        ConfigAction::List { show_origin } => {
+            print_entries(&Layers::load()?.entries(), show_origin);
+            Ok(())
+        }
+        ConfigAction::Edit => edit(),
+        ConfigAction::Path => {
+            println!("{}", config_path()?.display());
+            Ok(())
+        }
+    }
+}
+
+fn get(key: &str, show_origin: bool) -> Result<()> {
+    let key = layers::parse_key(key)?;
+    let entries: Vec<_> = Layers::load()?
+        .entries()
+        .into_iter()
+        .filter(|entry| entry.key.starts_with(&key))
+        .collect();
+
+    match entries.as_slice() {
+        [] => bail!("{} isn't set", layers::display_key(&key)),
+        // A single value is printed bare, for scripts
+        [entry] if entry.key == key && !show_origin => match &entry.value {
+            toml::Value::String(value) if !is_secret(&entry.key) => println!("{value}"),
+            _ => println!("{}", display_value(entry)),
+        },
+        entries => print_entries(entries, show_origin),
+    }
+    Ok(())
+}
+
+fn set(key: &str, raw: &str) -> Result<()> {
+    let key = layers::parse_key(key)?;
+    let Some(value) = layers::coerce(&key, raw)? else {
+        bail!("unknown setting {}", layers::display_key(&key));
+    };
+
+    // Read without validating, so a broken setting can be fixed
+    let mut user = layers::read_user()?;
+    layers::set(&mut user, &key, value)?;
+    write_valid(&user)?;
+
+    let env_override = Layers::load()
+        .ok()
+        .and_then(|loaded| loaded.env_override(&key).map(str::to_string));
+    if let Some(name) = env_override {
+        super::print_warning(&format!("${name} overrides this setting"));
+    }
+    Ok(())
+}
+
+fn unset(key: &str) -> Result<()> {
+    let key = layers::parse_key(key)?;
+    let mut user = layers::read_user()?;
+    if layers::remove(&mut user, &key).is_none() {
+        bail!(
+            "{} isn't set in {}",
+            layers::display_key(&key),
+            config_path()?.display()
+        );
+    }
+    write_valid(&user)
+}
+
+/// Write the user config file, unless the result would be invalid
+fn write_valid(user: &toml::Table) -> Result<()> {
+    toml::Value::Table(user.clone())
+        .try_into::<Config>()
+        .wrap_err("the config would be invalid")?;
+    layers::write_user(user)
+}
+
+fn edit() -> Result<()> {
+    let path = config_path()?;
+    if !path.exists() {
+        layers::write_user(&toml::Table::new())?;
+    }
+    let configured = Config::load().ok().and_then(|config| config.solve.editor);
+    let editor = crate::post_start::editor(configured.as_deref())
+        .unwrap_or_else(|| default_editor().to_string());
+
+    loop {
+        run_editor(&editor, &path)?;
+        let Err(err) = Layers::load() else {
+            return Ok(());
+        };
+        super::print_warning(&format!("{err:#}"));
+        if !Confirm::new("Edit it again?").with_default(true).prompt()? {
+            return Err(err);
+        }
+    }
+}
+
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("the editor command is empty");
+    };
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .wrap_err_with(|| format!("failed to run {program}"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+const fn default_editor() -> &'static str {
+    "vi"
+}
+
+#[cfg(not(unix))]
+const fn default_editor() -> &'static str {
+    "notepad"
+}
+
+fn print_entries(entries: &[Entry], show_origin: bool) {
+    let width = entries
+        .iter()
+        .map(|entry| entry.origin.to_string().len())
+        .max()
+        .unwrap_or_default();
+
+    for entry in entries {
+        let origin = if show_origin {
+            format!("{:<width$}  ", entry.origin.to_string())
+                .dimmed()
+                .to_string()
+        } else {
+            String::new()
+        };
+        let note = if entry.known {
+            String::new()
+        } else {
+            format!("  {}", "# unknown setting, ignored".yellow())
+        };
+        println!(
+            "{origin}{} = {}{note}",
+            layers::display_key(&entry.key),
+            display_value(entry)
+        );
+    }
+}
+
+pub(super) fn display_value(entry: &Entry) -> String {
+    if is_secret(&entry.key) {
+        return REDACTED.to_string();
+    }
+    entry.value.to_string()
+}
+
+fn is_secret(key: &Key) -> bool {
+    key.last().is_some_and(|name| {
+        ["token", "secret", "password"]
+            .iter()
+            .any(|secret| name.contains(secret))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PrMode, CONFIG_ENV};
+    use crate::test_env::EnvVar;
+
+    #[test]
+    fn test_set_fixes_invalid_value() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "version = 1\n\n[solve]\npr = \"bogus\"\n").unwrap();
+        let _config = EnvVar::set(CONFIG_ENV, &path);
+        assert!(Layers::load().is_err());
+
+        set("solve.pr", "manual").unwrap();
+        let config = Layers::load().unwrap().config().unwrap();
+        assert_eq!(config.solve.pr, PrMode::Manual);
+    }
+
+    #[tokio::test]
+    async fn test_config_command_runs_with_invalid_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "version = 1\n\n[solve]\npr = \"bogus\"\n").unwrap();
+        let _config = EnvVar::set(CONFIG_ENV, &path);
+
+        let action = ConfigAction::Set {
+            key: "solve.pr".to_string(),
+            value: "manual".to_string(),
+        };
+        crate::command::handle(crate::Command::Config { action })
+            .await
+            .unwrap();
+        assert_eq!(Config::load().unwrap().solve.pr, PrMode::Manual);
+    }
+
+    #[test]
+    fn test_debug_redacts_secret_values() {
+        let action = ConfigAction::Set {
+            key: "github_token".to_string(),
+            value: "ghp_secret".to_string(),
+        };
+        assert!(!format!("{action:?}").contains("ghp_secret"));
+
+        let action = ConfigAction::Set {
+            key: "solve.pr".to_string(),
+            value: "manual".to_string(),
+        };
+        assert!(format!("{action:?}").contains("manual"));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config::layers::Layers;
+use crate::config::Config;
+use crate::telemetry::Scrubber;
+use eyre::{Result, WrapErr};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// How many runs' logs go in a bundle
+const BUNDLED_LOGS: usize = 5;
+
+#[derive(clap::Subcommand, Debug)]
+pub enum DebugAction {
+    /// Zip recent logs, your settings and version info to attach to a bug report
+    ///
+    /// Tokens, URLs, your home directory and repository names are scrubbed.
+    Bundle {
+        /// Where to write the zip (defaults to bounty-debug-<time>.zip here)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn handle(action: DebugAction) -> Result<()> {
+    match action {
+        DebugAction::Bundle { output } => {
+            let output = output.unwrap_or_else(default_output);
+            bundle(&output)?;
+            super::print_success(&format!("Wrote {}", output.display()));
+            super::print_info("Have a look inside before attaching it to a bug report");
+            Ok(())
+        }
+    }
+}
+
+fn default_output() -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    PathBuf::from(format!("bounty-debug-{now}.zip"))
+}
+
+fn bundle(output: &Path) -> Result<()> {
+    let config = Config::load().ok();
+    let scrubber = Scrubber::load(config.as_ref());
+
+    let file =
+        File::create(output).wrap_err_with(|| format!("failed to create {}", output.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    zip.start_file("version.txt", options)?;
+    zip.write_all(scrubber.text(&version_info()).as_bytes())?;
+
+    zip.start_file("config.toml", options)?;
+    zip.write_all(scrubber.text(&settings()).as_bytes())?;
+
+    for log in crate::logging::log_files()?.iter().take(BUNDLED_LOGS) {
+        let contents = std::fs::read_to_string(log)
+            .wrap_err_with(|| format!("failed to read {}", log.display()))?;
+        let name = log.file_name().unwrap_or_default().to_string_lossy();
+        zip.start_file(format!("logs/{name}"), options)?;
+        zip.write_all(scrubber.text(&contents).as_bytes())?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn version_info() -> String {
+    let git = Command::new("git")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map_or_else(
+            || "not found".to_string(),
+            |output| String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        );
+    let shell = std::env::var("SHELL").unwrap_or_default();
+    format!(
+        "bounty {}\nos: {} {}\ngit: {git}\nshell: {shell}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    )
+}
+
+/// Every setting with where it comes from, secrets redacted; or why they couldn't be read
+fn settings() -> String {
+    let entries = match Layers::load() {
+        Ok(layers) => layers.entries(),
+        Err(err) => return format!("# the settings couldn't be read: {err:#}\n"),
+    };
+    entries.iter().fold(String::new(), |mut settings, entry| {
+        let _ = writeln!(
+            settings,
+            "{} = {}  # {}",
+            crate::config::layers::display_key(&entry.key),
+            super::config::display_value(entry),
+            entry.origin
+        );
+        settings
+    })
+}
@@ -0,0 +1,18 @@
+use super::abandon::remove_worktree;
+use super::pr::find_bounty;
+use crate::state::State;
+use eyre::Result;
+
+pub fn handle(issue_ref: Option<&str>) -> Result<()> {
+    let mut state = State::load()?;
+    let bounty = find_bounty(&state, issue_ref)?;
+
+    remove_worktree(&bounty)?;
+
+    let issue = bounty.repo_issue();
+    state.remove(&issue);
+    state.save()?;
+    super::print_success(&format!("Finished {issue}"));
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use crate::state::State;
+use crate::GitHub;
+use eyre::Result;
+use owo_colors::OwoColorize;
+
+/// List tracked bounties, refreshing the pull request status `bounty prompt` shows
+pub async fn handle(github: GitHub) -> Result<()> {
+    let mut state = State::load()?;
+    if state.bounties.is_empty() {
+        println!("Not working on any bounties. Start one with `bounty solve`.");
+        return Ok(());
+    }
+
+    for bounty in &mut state.bounties {
+        let Some(pr_number) = bounty.pr_number else {
+            continue;
+        };
+        match github
+            .pr_status(&bounty.owner, &bounty.repo, pr_number)
+            .await
+        {
+            Ok((pr_state, ci)) => {
+                bounty.pr_state = Some(pr_state);
+                bounty.ci = ci;
+            }
+            Err(err) => super::print_warning(&format!("{}: {err:#}", bounty.repo_issue())),
+        }
+    }
+    state.save()?;
+
+    for bounty in &state.bounties {
+        let status = bounty
+            .pr_state
+            .map_or_else(|| "no PR".to_string(), |pr_state| pr_state.to_string());
+        let ci = bounty.ci.map_or("", |ci| ci.symbol());
+        let checkout = bounty
+            .checkout
+            .as_ref()
+            .map(|checkout| checkout.display().to_string())
+            .unwrap_or_default();
+        println!(
+            "{:<30} {:<7} {ci:<1}  {}",
+            bounty.repo_issue().bold(),
+            status,
+            checkout.dimmed()
+        );
+    }
+    Ok(())
+}
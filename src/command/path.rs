@@ -0,0 +1,34 @@
+use super::pr::find_bounty;
+use crate::state::{State, TrackedBounty};
+use eyre::{bail, Result};
+
+pub fn handle(issue_ref: Option<&str>) -> Result<()> {
+    let state = State::load()?;
+    let bounty = resolve(&state, issue_ref)?;
+    let Some(checkout) = bounty.checkout else {
+        bail!("{} has no checkout", bounty.repo_issue());
+    };
+    println!("{}", checkout.display());
+    Ok(())
+}
+
+/// Like [`find_bounty`], but also accepts a bare issue number when it's unambiguous
+fn resolve(state: &State, issue_ref: Option<&str>) -> Result<TrackedBounty> {
+    let number: Option<u64> =
+        issue_ref.and_then(|issue_ref| issue_ref.trim_start_matches('#').parse().ok());
+    let Some(number) = number else {
+        return find_bounty(state, issue_ref);
+    };
+
+    let mut matching = state
+        .bounties
+        .iter()
+        .filter(|bounty| bounty.issue_number == number);
+    match (matching.next(), matching.next()) {
+        (Some(bounty), None) => Ok(bounty.clone()),
+        (None, _) => bail!("not working on any issue #{number}"),
+        (Some(_), Some(_)) => {
+            bail!("several bounties are issue #{number}; use owner/repo#{number}")
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use super::start::{open_draft_pr, remove_pr_hook, resolve_templates, spinner};
+use crate::git;
+use crate::state::{State, TrackedBounty};
+use crate::template::IssueVars;
+use crate::{Config, GitHub, RepoIssue};
+use eyre::{bail, Result};
+use std::time::Duration;
+
+/// How often `--after-push` checks whether the branch reached the fork
+const PUSH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How many times `--after-push` checks before giving up
+const PUSH_POLL_ATTEMPTS: u32 = 30;
+
+pub async fn handle(
+    issue_ref: Option<String>,
+    after_push: Option<Vec<String>>,
+    github: GitHub,
+) -> Result<()> {
+    let config = Config::load()?;
+    let mut state = State::load()?;
+    let Some(refs) = after_push else {
+        let bounty = find_bounty(&state, issue_ref.as_deref())?;
+        return open(&github, &config, &mut state, bounty).await;
+    };
+
+    for bounty in pushed_bounties(&state, &refs)? {
+        wait_for_push(&github, &bounty).await?;
+        open(&github, &config, &mut state, bounty).await?;
+    }
+    Ok(())
+}
+
+/// Open the draft pull request for `bounty` and record it
+async fn open(
+    github: &GitHub,
+    config: &Config,
+    state: &mut State,
+    mut bounty: TrackedBounty,
+) -> Result<()> {
+    let multi = crate::logging::progress();
+    let status_pb = spinner(&multi);
+
+    let TrackedBounty {
+        owner,
+        repo,
+        issue_number,
+        ..
+    } = bounty.clone();
+    let user = github.current_user().await?.login;
+    let issue = github.get_issue(&owner, &repo, issue_number).await?;
+    let templates = resolve_templates(github, config, &owner, &repo)
+        .await?
+        .render(&IssueVars::from_issue(&owner, &repo, &issue));
+
+    open_draft_pr(github, config, &mut bounty, &templates, &user, &status_pb).await?;
+
+    let pr_url = bounty.pr_url.clone().unwrap_or_default();
+    let checkout = bounty.checkout.clone();
+    state.upsert(bounty);
+    state.save()?;
+    if let Some(checkout) = &checkout {
+        remove_pr_hook(state, checkout)?;
+    }
+
+    status_pb.finish_with_message(format!(
+        "✨ Draft pull request ready for issue #{issue_number}"
+    ));
+    println!("\n📝 Pull Request: {pr_url}");
+    Ok(())
+}
+
+/// The bounty named by `issue_ref`, or the one checked out in the current directory
+pub(super) fn find_bounty(state: &State, issue_ref: Option<&str>) -> Result<TrackedBounty> {
+    if let Some(issue_ref) = issue_ref {
+        let issue = RepoIssue::parse(issue_ref)?;
+        return state
+            .find(&issue)
+            .cloned()
+            .ok_or_else(|| eyre::eyre!("not working on {issue}"));
+    }
+
+    let cwd = std::env::current_dir()?;
+    state
+        .find_by_path(&cwd, git::head_branch(&cwd).as_deref())?
+        .cloned()
+        .ok_or_else(|| eyre::eyre!("not inside a bounty checkout; pass the issue reference"))
+}
+
+/// Bounties in the current checkout waiting for the push of one of the local `refs` to open
+/// their draft PR
+///
+/// The hook is shared by every bounty in the clone. Hooks installed by older versions don't
+/// pass the refs, so then it's the bounty on the checked-out branch.
+fn pushed_bounties(state: &State, refs: &[String]) -> Result<Vec<TrackedBounty>> {
+    let cwd = std::env::current_dir()?;
+    let branches: Vec<String> = if refs.is_empty() {
+        git::head_branch(&cwd).into_iter().collect()
+    } else {
+        refs.iter()
+            .filter_map(|local_ref| local_ref.strip_prefix("refs/heads/"))
+            .map(String::from)
+            .collect()
+    };
+
+    Ok(state
+        .bounties
+        .iter()
+        .filter(|bounty| bounty.pr_on_push && bounty.pr_number.is_none())
+        .filter(|bounty| branches.contains(&bounty.branch))
+        .filter(|bounty| {
+            bounty
+                .checkout
+                .as_deref()
+                .is_some_and(|checkout| cwd.starts_with(checkout))
+        })
+        .cloned()
+        .collect())
+}
+
+/// Called from the pre-push hook, which runs before the push actually happens
+async fn wait_for_push(github: &GitHub, bounty: &TrackedBounty) -> Result<()> {
+    for _ in 0..PUSH_POLL_ATTEMPTS {
+        if github
+            .branch_exists(&bounty.fork_owner, &bounty.repo, &bounty.branch)
+            .await?
+        {
+            return Ok(());
+        }
+        tokio::time::sleep(PUSH_POLL_INTERVAL).await;
+    }
+    bail!("branch {} never appeared on the fork", bounty.branch)
+}
@@ -0,0 +1,24 @@
+use crate::git;
+use crate::state::State;
+use eyre::Result;
+
+/// Print the bounty checked out in the current directory, using only cached state
+pub fn handle() -> Result<()> {
+    let state = State::load()?;
+    let cwd = std::env::current_dir()?;
+    // Nothing rather than an error on every prompt when several bounties share the checkout
+    let Ok(Some(bounty)) = state.find_by_path(&cwd, git::head_branch(&cwd).as_deref()) else {
+        return Ok(());
+    };
+
+    let segment = [
+        Some(bounty.repo_issue().to_string()),
+        bounty.pr_state.map(|pr_state| pr_state.to_string()),
+        bounty.ci.map(|ci| ci.symbol().to_string()),
+    ];
+    println!(
+        "{}",
+        segment.into_iter().flatten().collect::<Vec<_>>().join(" ")
+    );
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use crate::config::{config_path, Config, PrMode, Transport};
+use crate::watch::QuestFilter;
+use crate::CLIENT_ID;
+use eyre::Result;
+use inquire::{Confirm, Select, Text};
+use owo_colors::OwoColorize;
+use std::path::PathBuf;
+
+const TRANSPORTS: &[(Transport, &str)] = &[
+    (Transport::Token, "HTTPS with your bounty login"),
+    (Transport::Ssh, "SSH with ssh-agent or your ~/.ssh keys"),
+    (
+        Transport::CredentialHelper,
+        "HTTPS with your git credential helper",
+    ),
+];
+
+const PR_MODES: &[(PrMode, &str)] = &[
+    (PrMode::Now, "right away, as a draft"),
+    (PrMode::OnFirstPush, "when you first push the branch"),
+    (PrMode::Manual, "only when you run `bounty pr`"),
+];
+
+pub async fn handle() -> Result<()> {
+    let mut config = Config::load()?;
+    run(&mut config).await
+}
+
+/// Walk through the main settings, logging in if there's no token yet, then save them and
+/// show a summary
+pub async fn run(config: &mut Config) -> Result<()> {
+    super::print_step("Workspace");
+    config.workspace_dir = Some(ask_workspace_dir(config)?);
+    config.solve.editor = ask_editor(config.solve.editor.as_deref())?;
+
+    super::print_step("Git and pull requests");
+    config.git.transport = select(
+        "How should bounty clone and push?",
+        TRANSPORTS,
+        config.git.transport,
+    )?;
+    config.solve.pr = select(
+        "When should the pull request be opened?",
+        PR_MODES,
+        config.solve.pr,
+    )?;
+    config.templates.use_repo_pr_template = Some(
+        Confirm::new("Start pull requests with the repository's pull request template?")
+            .with_default(config.templates.use_repo_pr_template.unwrap_or(true))
+            .prompt()?,
+    );
+
+    super::print_step("Quests");
+    ask_quest_filters(config)?;
+
+    super::print_step("Privacy");
+    config.telemetry.enabled = Some(
+        Confirm::new("Send crash reports to help fix bugs?")
+            .with_help_message(
+                "Tokens, paths, URLs and repository names are scrubbed; \
+                 see one with `bounty telemetry preview`",
+            )
+            .with_default(config.telemetry.enabled.unwrap_or(false))
+            .prompt()?,
+    );
+
+    super::print_step("Shell");
+    super::completion::offer_install()?;
+
+    if config.try_get_github_token().is_none() {
+        super::print_step("Setting up GitHub access");
+        config.github_token = Some(crate::github::login(CLIENT_ID).await?);
+    }
+
+    config.complete_first_time_setup()?;
+    print_summary(config)
+}
+
+fn ask_workspace_dir(config: &Config) -> Result<PathBuf> {
+    let current = config.workspace_dir()?;
+    let answer = Text::new("Where should bounties be checked out?")
+        .with_default(&current.display().to_string())
+        .prompt()?;
+    Ok(expand_home(answer.trim()))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn ask_editor(current: Option<&str>) -> Result<Option<String>> {
+    let answer = Text::new("Editor to open bounties in:")
+        .with_initial_value(current.unwrap_or_default())
+        .with_help_message("e.g. `code` or `zed`; leave empty to use $VISUAL or $EDITOR")
+        .prompt()?;
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+fn ask_quest_filters(config: &mut Config) -> Result<()> {
+    let labels = Text::new("GitHub labels that mark bounties:")
+        .with_initial_value(&config.sources.github_labels.join(", "))
+        .with_help_message("comma-separated, e.g. bounty, 💎 Bounty; leave empty to skip")
+        .prompt()?;
+    config.sources.github_labels = split_list(&labels);
+
+    let current: Vec<_> = config
+        .watch
+        .filters
+        .iter()
+        .filter(|filter| filter.repo.is_none())
+        .flat_map(|filter| filter.keywords.clone())
+        .collect();
+    let keywords = Text::new("Only notify about quests mentioning:")
+        .with_initial_value(&current.join(", "))
+        .with_help_message("comma-separated keywords; leave empty to hear about every quest")
+        .prompt()?;
+
+    config.watch.filters.retain(|filter| filter.repo.is_some());
+    let keywords = split_list(&keywords);
+    if !keywords.is_empty() {
+        config.watch.filters.push(QuestFilter {
+            repo: None,
+            keywords,
+        });
+    }
+    Ok(())
+}
+
+fn split_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Pick one of `choices`, starting on `current`
+fn select<T: Copy + PartialEq>(message: &str, choices: &[(T, &str)], current: T) -> Result<T> {
+    let labels: Vec<&str> = choices.iter().map(|(_, label)| *label).collect();
+    let cursor = choices
+        .iter()
+        .position(|(value, _)| *value == current)
+        .unwrap_or_default();
+    let picked = Select::new(message, labels)
+        .with_starting_cursor(cursor)
+        .raw_prompt()?;
+    Ok(choices[picked.index].0)
+}
+
+fn label<T: PartialEq>(choices: &[(T, &'static str)], value: &T) -> &'static str {
+    choices
+        .iter()
+        .find(|(choice, _)| choice == value)
+        .map_or("", |(_, label)| label)
+}
+
+fn print_summary(config: &Config) -> Result<()> {
+    let or_none = |list: &[String]| {
+        if list.is_empty() {
+            "none".to_string()
+        } else {
+            list.join(", ")
+        }
+    };
+    let keywords: Vec<_> = config
+        .watch
+        .filters
+        .iter()
+        .filter(|filter| filter.repo.is_none())
+        .flat_map(|filter| filter.keywords.clone())
+        .collect();
+    let template = if config.templates.use_repo_pr_template == Some(false) {
+        "without"
+    } else {
+        "with"
+    };
+
+    let rows = [
+        ("Workspace", config.workspace_dir()?.display().to_string()),
+        (
+            "Editor",
+            config
+                .solve
+                .editor
+                .clone()
+                .unwrap_or_else(|| "$VISUAL / $EDITOR".to_string()),
+        ),
+        ("Git", label(TRANSPORTS, &config.git.transport).to_string()),
+        (
+            "Pull requests",
+            format!(
+                "{}, {template} the repository's template",
+                label(PR_MODES, &config.solve.pr)
+            ),
+        ),
+        ("Quest labels", or_none(&config.sources.github_labels)),
+        ("Keywords", or_none(&keywords)),
+        (
+            "Crash reports",
+            if config.telemetry.is_enabled() {
+                "on"
+            } else {
+                "off"
+            }
+            .to_string(),
+        ),
+    ];
+
+    super::print_step("Summary");
+    for (name, value) in rows {
+        println!("  {:<14} {value}", name.dimmed());
+    }
+    println!(
+        "\n  Saved to {}; run `bounty setup` to change it",
+        config_path()?.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_list() {
+        assert_eq!(
+            split_list(" bounty, 💎 Bounty ,,"),
+            vec!["bounty".to_string(), "💎 Bounty".to_string()]
+        );
+        assert!(split_list("  ").is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use clap_complete::Shell;
+use eyre::{bail, Result};
+
+/// Wraps `bounty` so `bounty cd <issue-ref>` can change the shell's directory
+const POSIX_INIT: &str = r#"bounty() {
+    if [ "$1" = "cd" ]; then
+        shift
+        local dir
+        dir="$(command bounty path "$@")" && cd "$dir"
+    else
+        command bounty "$@"
+    fi
+}
+"#;
+
+const FISH_INIT: &str = r#"function bounty --wraps bounty
+    if test "$argv[1]" = cd
+        set -l dir (command bounty path $argv[2..-1]); and cd $dir
+    else
+        command bounty $argv
+    end
+end
+"#;
+
+pub fn handle(shell: Shell) -> Result<()> {
+    let init = match shell {
+        Shell::Bash | Shell::Zsh => POSIX_INIT,
+        Shell::Fish => FISH_INIT,
+        _ => bail!("shell integration isn't available for {shell} yet"),
+    };
+    print!("{init}");
+    Ok(())
+}
@@ -1,18 +1,128 @@
-use crate::{GitHub, RepoIssue};
+use super::start::StartOptions;
+use crate::config::{PostStartAction, PrMode};
+use crate::post_start;
+use crate::sources::configured_sources;
+use crate::{Config, GitHub, RepoIssue};
 use eyre::Result;
+use std::path::PathBuf;
 
-pub async fn handle(issue_ref: Option<String>, github: GitHub) -> Result<()> {
-    let issue_ref = if let Some(issue_ref) = issue_ref {
-        issue_ref
+#[derive(clap::Args, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SolveArgs {
+    /// Issue reference in any of these formats:
+    ///
+    /// - https://github.com/owner/repo/issues/123
+    ///
+    /// - github.com/owner/repo/issues/123
+    ///
+    /// - owner/repo/issues/123
+    ///
+    /// - owner/repo/123
+    ///
+    /// - owner/repo#123
+    #[arg(required = false)]
+    pub issue_ref: Option<String>,
+
+    /// Start even if the issue is closed, locked, assigned or has competing pull requests
+    #[arg(long, short)]
+    pub force: bool,
+
+    /// Don't open a draft pull request; run `bounty pr` when you're ready
+    #[arg(long)]
+    pub no_pr: bool,
+
+    /// Don't create the empty bootstrap commit
+    #[arg(long)]
+    pub no_commit: bool,
+
+    /// Open the draft pull request automatically the first time you push the branch
+    #[arg(long, conflicts_with = "no_pr")]
+    pub pr_on_first_push: bool,
+
+    /// Work in this existing clone of the repository instead of cloning the fork
+    /// (defaults to the clone you're in, if it's the right repository)
+    #[arg(long, value_name = "PATH")]
+    pub repo_path: Option<PathBuf>,
+
+    /// Start from and target this upstream branch instead of the default branch
+    #[arg(long, value_name = "BRANCH")]
+    pub base: Option<String>,
+
+    /// Update your fork with GitHub's "Sync fork" before branching
+    #[arg(long)]
+    pub sync_fork: bool,
+
+    /// What to do once the bounty is set up, instead of `solve.after` (can be repeated)
+    #[arg(long, value_enum, value_name = "ACTION")]
+    pub open: Vec<PostStartAction>,
+
+    /// Print a `cd` into the checkout for your shell to run: eval "$(bounty solve ... --shell)"
+    #[arg(long)]
+    pub shell: bool,
+}
+
+impl SolveArgs {
+    /// Combine the flags with the `[solve]` config, flags taking precedence
+    fn start_options(&self, config: &Config) -> StartOptions {
+        let pr = if self.pr_on_first_push {
+            PrMode::OnFirstPush
+        } else if self.no_pr {
+            PrMode::Manual
+        } else {
+            config.solve.pr
+        };
+
+        StartOptions {
+            force: self.force,
+            // The PR is opened by the user's own first push, so there's nothing to bootstrap
+            commit: config.solve.commit && !self.no_commit && pr != PrMode::OnFirstPush,
+            pr,
+            repo_path: self.repo_path.clone(),
+            base: self.base.clone(),
+            sync_fork: self.sync_fork || config.solve.sync_fork,
+            shell: self.shell,
+        }
+    }
+}
+
+pub async fn handle(args: SolveArgs, github: GitHub) -> Result<()> {
+    let config = Config::load()?;
+    let issue_ref = if let Some(issue_ref) = &args.issue_ref {
+        issue_ref.clone()
     } else {
-        crate::issue::prompt_issue_reference().await?
+        let sources = configured_sources(&config.sources, Some(github.clone()));
+        crate::issue::prompt_issue_reference(&sources).await?
     };
 
     let repo_issue = RepoIssue::parse(&issue_ref)?;
-    super::start::start_bounty(
+    let bounty = Box::pin(super::start::start_bounty(
         &repo_issue.full_repo_name(),
         repo_issue.issue_number,
         github,
-    )
-    .await
+        &config,
+        args.start_options(&config),
+    ))
+    .await?;
+
+    let actions = if args.open.is_empty() {
+        &config.solve.after
+    } else {
+        &args.open
+    };
+    for &action in actions {
+        if args.shell && action == PostStartAction::Shell {
+            super::print_warning("not starting a shell with --shell; your shell will cd instead");
+            continue;
+        }
+        if let Err(err) = post_start::run(action, &bounty, config.solve.editor.as_deref()) {
+            super::print_warning(&format!("{err:#}"));
+        }
+    }
+
+    if args.shell {
+        if let Some(checkout) = &bounty.checkout {
+            println!("{}", post_start::cd_command(checkout));
+        }
+    }
+    Ok(())
 }
@@ -1,16 +1,67 @@
-use crate::GitHub;
-use eyre::WrapErr;
-use git2::{Cred, PushOptions, RemoteCallbacks, Repository};
+use crate::branch;
+use crate::config::{CloneConfig, GitConfig, PrMode, ProjectConfig, Transport};
+use crate::git::{self, Identity, IdentitySources, Signing};
+use crate::hooks;
+use crate::pr_template;
+use crate::state::{PrState, State, TrackedBounty};
+use crate::template::{render, IssueVars, Templates};
+use crate::{Config, GitHub};
+use eyre::{bail, WrapErr};
+use git2::{Oid, PushOptions, Repository};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use octocrab::models::issues::Issue;
+use octocrab::models::UserProfile;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use tracing::{debug, instrument};
+use tracing::{debug, instrument, warn};
 
-#[instrument(skip(github), fields(owner, repo))]
+/// Options controlling how work on a bounty is started
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct StartOptions {
+    /// Start even if the issue looks closed, locked, assigned or already being worked on
+    pub force: bool,
+    /// Create and push the empty bootstrap commit
+    pub commit: bool,
+    /// When to open the draft pull request
+    pub pr: PrMode,
+    /// An existing clone to work in instead of the workspace
+    pub repo_path: Option<PathBuf>,
+    /// Upstream branch to start from and target, instead of the default branch
+    pub base: Option<String>,
+    /// Update the fork's base branch with GitHub's "Sync fork" first
+    pub sync_fork: bool,
+    /// Keep stdout free for a `cd` command to `eval`
+    pub shell: bool,
+}
+
+impl Default for StartOptions {
+    fn default() -> Self {
+        Self {
+            force: false,
+            commit: true,
+            pr: PrMode::Now,
+            repo_path: None,
+            base: None,
+            sync_fork: false,
+            shell: false,
+        }
+    }
+}
+
+/// Marks the pre-push hook installed for `--pr-on-first-push`, so we only ever remove our own
+const PR_HOOK_MARKER: &str = "# installed by bounty: open draft PR on first push";
+
+#[instrument(skip(github, config, options), fields(owner, repo))]
 pub async fn start_bounty(
     repo_full_name: &str,
     issue_number: u64,
     github: GitHub,
-) -> eyre::Result<()> {
+    config: &Config,
+    options: StartOptions,
+) -> eyre::Result<TrackedBounty> {
     let (owner, repo) = repo_full_name
         .split_once('/')
         .ok_or_else(|| eyre::eyre!("Invalid repo format. Expected 'owner/repo'"))?;
@@ -19,20 +70,22 @@ pub async fn start_bounty(
     tracing::Span::current().record("owner", owner);
     tracing::Span::current().record("repo", repo);
 
-    let multi = MultiProgress::new();
-    let spinner_style = ProgressStyle::with_template("{spinner:.green} {msg:.bold.dim}")
-        .unwrap()
-        .tick_chars("⣾⣽⣻⢿⡿⣟⣯⣷");
-
-    let status_pb = multi.add(ProgressBar::new_spinner());
-    status_pb.set_style(spinner_style);
-    status_pb.enable_steady_tick(Duration::from_millis(80));
+    let multi = crate::logging::progress();
+    let status_pb = spinner(&multi);
 
     status_pb.set_message(format!(
         "Starting work on bounty for {owner}/{repo}#{issue_number}"
     ));
 
-    debug!("initializing GitHub client");
+    status_pb.set_message("Checking issue eligibility...");
+    let profile = github.current_user().await?;
+    let user = profile.login.clone();
+    let issue = github.get_issue(owner, repo, issue_number).await?;
+    check_eligibility(&github, &issue, owner, repo, &user, &options, &status_pb).await?;
+
+    let templates = resolve_templates(&github, config, owner, repo)
+        .await?
+        .render(&IssueVars::from_issue(owner, repo, &issue));
 
     status_pb.set_message("Creating fork of repository...");
     let fork_owner = github.fork_repo(owner, repo).await?;
@@ -40,110 +93,947 @@ pub async fn start_bounty(
     debug!(?fork_owner, "fork created/exists");
     status_pb.set_message("✓ Fork created successfully");
 
-    // Clone the repository
-    let repo_url = format!("https://github.com/{fork_owner}/{repo}.git");
-    let temp_dir = tempfile::tempdir()?;
-    status_pb.set_message("Cloning repository...");
+    // Get repository info to find default branch
+    status_pb.set_message("Getting repository info...");
+    let repo_info = github.get_repo_info(owner, repo).await?;
 
-    let mut callbacks = RemoteCallbacks::new();
-    let token = github.token();
-    callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
-        Cred::userpass_plaintext("git", token)
+    let base_branch = options.base.clone().unwrap_or_else(|| {
+        repo_info
+            .default_branch
+            .unwrap_or_else(|| "main".to_string())
     });
 
-    let mut fetch_options = git2::FetchOptions::new();
-    let mut fetch_callbacks = RemoteCallbacks::new();
-    let token = github.token();
-    fetch_callbacks.credentials(move |_url, _username_from_url, _allowed_types| {
-        Cred::userpass_plaintext("git", token)
-    });
-    fetch_options.remote_callbacks(fetch_callbacks);
+    if options.sync_fork && fork_owner != owner {
+        status_pb.set_message("Syncing fork with upstream...");
+        if let Err(err) = github.sync_fork(&fork_owner, repo, &base_branch).await {
+            // Only a convenience, the branch is created from upstream either way
+            status_pb.suspend(|| super::print_warning(&format!("{err:#}")));
+        }
+    }
+
+    let checkout = prepare_checkout(
+        &multi,
+        &github,
+        config,
+        &options,
+        (owner, repo, issue_number),
+        (&fork_owner, &base_branch),
+        &status_pb,
+    )?;
+
+    let (branch_name, tip) = create_branch(
+        &checkout,
+        &github,
+        &profile,
+        &templates,
+        options.commit,
+        &config.git,
+        &status_pb,
+    )?;
+    let failed_hooks = enter_branch(
+        &multi,
+        config,
+        &checkout,
+        &branch_name,
+        (owner, repo),
+        &status_pb,
+    )?;
+
+    let mut bounty = TrackedBounty {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        issue_number,
+        fork_owner: fork_owner.clone(),
+        branch: branch_name,
+        base: base_branch,
+        checkout: Some(checkout.path.clone()),
+        pr_number: None,
+        pr_url: None,
+        claim_comment_id: None,
+        self_assigned: false,
+        pr_on_push: false,
+        failed_hooks,
+        pr_state: None,
+        ci: None,
+    };
+
+    let pr_mode = effective_pr_mode(options.pr, tip != checkout.base, &status_pb);
+    track_bounty(
+        &github,
+        config,
+        &mut bounty,
+        &templates,
+        &user,
+        pr_mode,
+        &status_pb,
+    )
+    .await?;
+
+    // Only for the summary, so it mustn't fail a bounty that's already set up
+    let contributing = pr_template::contributing_guide(&github, owner, repo)
+        .await
+        .inspect_err(|err| debug!("failed to look for a contributing guide: {err:#}"))
+        .ok()
+        .flatten();
+
+    status_pb.finish_with_message(format!("✨ Ready to work on issue #{issue_number}"));
+
+    // With `--shell`, stdout is reserved for the `cd` command
+    let mut out: Box<dyn Write> = if options.shell {
+        Box::new(std::io::stderr())
+    } else {
+        Box::new(std::io::stdout())
+    };
+    print_summary(&mut out, &bounty, pr_mode, contributing.as_deref())?;
 
-    let git_repo =
-        Repository::clone(&repo_url, temp_dir.path()).wrap_err("failed to clone repository")?;
+    Ok(bounty)
+}
+
+/// Create the bounty branch, with the bootstrap commit if wanted, and push it to the fork,
+/// returning its name and tip
+fn create_branch(
+    checkout: &Checkout,
+    github: &GitHub,
+    profile: &UserProfile,
+    templates: &Templates,
+    commit: bool,
+    git_settings: &GitConfig,
+    status_pb: &ProgressBar,
+) -> eyre::Result<(String, Oid)> {
+    let Checkout {
+        git_repo,
+        fork_remote,
+        transport,
+        base: checkout_base,
+        strategy,
+        ..
+    } = checkout;
+    let transport = *transport;
+
+    let wanted_branch = templates.branch.clone();
+    if !git2::Reference::is_valid_name(&format!("refs/heads/{wanted_branch}")) {
+        bail!("branch template produced an invalid branch name: {wanted_branch:?}");
+    }
+    status_pb.set_message(format!("Checking for existing {wanted_branch} branches..."));
+    let remote_heads = git::remote_heads(git_repo, fork_remote, transport, github.token())?;
+    let mut fetch = |branch: &str| {
+        git::fetch_branch(
+            git_repo,
+            fork_remote,
+            branch,
+            transport,
+            github.token(),
+            strategy,
+        )
+    };
+    let branch = branch::plan(
+        git_repo,
+        *checkout_base,
+        &wanted_branch,
+        &remote_heads,
+        &my_emails(git_repo, git_settings, profile),
+        &mut fetch,
+    )?;
+    if let Some(note) = &branch.note {
+        status_pb.suspend(|| super::print_info(note));
+    }
 
-    // Create and checkout new branch
-    let branch_name = format!("issue-{issue_number}");
+    let branch_name = branch.name.clone();
     status_pb.set_message(format!("Creating branch {branch_name}..."));
+    let refname = format!("refs/heads/{branch_name}");
+    move_branch(git_repo, &refname, branch.tip)?;
+
+    let tip = if commit && !branch.has_work {
+        // Only needed now, so a broken signing setup doesn't stop `--no-commit`
+        let (identity, signing) = commit_settings(git_repo, git_settings, profile)?;
+        // Create empty commit
+        let head = git_repo.find_commit(branch.tip)?;
+        let tree = git_repo.find_tree(head.tree_id())?;
+
+        git::commit(
+            git_repo,
+            &refname,
+            &identity,
+            signing.as_ref(),
+            &templates.commit_message,
+            &tree,
+            &[&head],
+        )?
+    } else {
+        branch.tip
+    };
+
+    if tip != *checkout_base && remote_heads.get(&branch_name) != Some(&tip) {
+        status_pb.set_message("Pushing branch...");
+        push_branch(github, transport, git_repo, fork_remote, &branch_name)?;
+    }
+    Ok((branch_name, tip))
+}
+
+/// Check out the bounty branch (in a new worktree, if wanted) and run the post-checkout hooks,
+/// returning the ones that failed
+fn enter_branch(
+    multi: &MultiProgress,
+    config: &Config,
+    checkout: &Checkout,
+    branch_name: &str,
+    (owner, repo): (&str, &str),
+    status_pb: &ProgressBar,
+) -> eyre::Result<Vec<String>> {
+    if checkout.worktree {
+        status_pb.set_message("Adding worktree...");
+        git::add_worktree(&checkout.git_repo, &checkout.path, branch_name)?;
+    } else {
+        checkout_branch(&checkout.git_repo, branch_name)?;
+    }
+    set_upstream(&checkout.git_repo, &checkout.fork_remote, branch_name)?;
+
+    Ok(run_hooks(
+        multi,
+        status_pb,
+        config,
+        (owner, repo),
+        &checkout.path,
+    ))
+}
+
+/// Open the draft PR as `pr_mode` says and save the bounty, keeping the claim of an earlier run
+async fn track_bounty(
+    github: &GitHub,
+    config: &Config,
+    bounty: &mut TrackedBounty,
+    templates: &Templates,
+    user: &str,
+    pr_mode: PrMode,
+    status_pb: &ProgressBar,
+) -> eyre::Result<()> {
+    let mut state = State::load()?;
+    if let Some(previous) = state.find(&bounty.repo_issue()) {
+        // Already claimed on an earlier run, don't comment twice
+        bounty.claim_comment_id = previous.claim_comment_id;
+        bounty.self_assigned = previous.self_assigned;
+    }
+
+    match pr_mode {
+        PrMode::Now => open_draft_pr(github, config, bounty, templates, user, status_pb).await?,
+        PrMode::OnFirstPush => {
+            if let Some(checkout) = &bounty.checkout {
+                install_pr_hook(checkout)?;
+            }
+            bounty.pr_on_push = true;
+        }
+        PrMode::Manual => {}
+    }
+
+    state.upsert(bounty.clone());
+    state.save()
+}
+
+/// Print final status in a clean way
+fn print_summary(
+    out: &mut dyn Write,
+    bounty: &TrackedBounty,
+    pr_mode: PrMode,
+    contributing: Option<&str>,
+) -> std::io::Result<()> {
+    let TrackedBounty {
+        repo,
+        fork_owner,
+        branch,
+        ..
+    } = bounty;
+
+    writeln!(out, "\n🔗 Issue: {}", bounty.repo_issue().html_url())?;
+    writeln!(
+        out,
+        "🌿 Branch: https://github.com/{fork_owner}/{repo}/tree/{branch}"
+    )?;
+    if let Some(checkout) = &bounty.checkout {
+        writeln!(out, "📂 Checkout: {}", checkout.display())?;
+    }
+    match (&bounty.pr_url, pr_mode) {
+        (Some(pr_url), _) => writeln!(out, "📝 Pull Request: {pr_url}")?,
+        (None, PrMode::OnFirstPush) => writeln!(
+            out,
+            "📝 Pull Request: opened automatically when you first push {branch}"
+        )?,
+        (None, _) => writeln!(
+            out,
+            "📝 Pull Request: run `bounty pr` once you have commits"
+        )?,
+    }
+    if let Some(contributing) = contributing {
+        writeln!(out, "📖 Contributing guide: {contributing}")?;
+    }
+    Ok(())
+}
+
+/// A spinner on `multi` in the style shared by the start-like commands
+pub(super) fn spinner(multi: &MultiProgress) -> ProgressBar {
+    let spinner_style = ProgressStyle::with_template("{spinner:.green} {msg:.bold.dim}")
+        .unwrap()
+        .tick_chars("⣾⣽⣻⢿⡿⣟⣯⣷");
+
+    let status_pb = multi.add(ProgressBar::new_spinner());
+    status_pb.set_style(spinner_style);
+    status_pb.enable_steady_tick(Duration::from_millis(80));
+    status_pb
+}
+
+/// GitHub can't open a pull request without commits, so skipping the bootstrap commit
+/// postpones an immediate PR until `bounty pr`
+fn effective_pr_mode(pr: PrMode, has_commits: bool, status_pb: &ProgressBar) -> PrMode {
+    if has_commits || pr != PrMode::Now {
+        return pr;
+    }
+    status_pb.suspend(|| {
+        super::print_warning("no bootstrap commit, so the draft PR will wait for `bounty pr`");
+    });
+    PrMode::Manual
+}
+
+/// The repository the bounty branch is created in
+struct Checkout {
+    git_repo: Repository,
+    path: PathBuf,
+    /// Remote pointing at the fork, which the branch is pushed to
+    fork_remote: String,
+    /// Transport for the configured repository, used when a remote's URL doesn't say otherwise
+    transport: Transport,
+    /// Commit the bounty branch starts from
+    base: Oid,
+    /// How much of the fork to fetch when looking at its branches
+    strategy: CloneConfig,
+    /// Whether `path` is a worktree still to be added to `git_repo`
+    worktree: bool,
+}
+
+/// Use an existing clone of the repository when there is one, otherwise a workspace checkout
+fn prepare_checkout(
+    multi: &MultiProgress,
+    github: &GitHub,
+    config: &Config,
+    options: &StartOptions,
+    (owner, repo, issue_number): (&str, &str, u64),
+    (fork_owner, base_branch): (&str, &str),
+    status_pb: &ProgressBar,
+) -> eyre::Result<Checkout> {
+    let transport = config.transport_for(owner, repo);
+    let strategy = config.clone_strategy_for(owner, repo);
+
+    if let Some(git_repo) = local_clone(options, owner, repo, fork_owner)? {
+        status_pb.set_message("Using existing clone...");
+        return use_local_clone(
+            git_repo,
+            github,
+            (transport, strategy),
+            (owner, repo),
+            fork_owner,
+            base_branch,
+        );
+    }
+
+    // Clone the fork into the workspace
+    let repo_dir = config.workspace_dir()?.join(owner);
+    let path = repo_dir.join(format!("{repo}-{issue_number}"));
+    let worktree = config.solve.worktrees && !path.exists();
+    // Worktrees share one clone of the repository
+    let clone_path = if worktree {
+        repo_dir.join(repo)
+    } else {
+        path.clone()
+    };
+
+    status_pb.set_message("Cloning repository...");
+    let url = git::remote_url(transport, fork_owner, repo);
+    let git_repo = open_or_clone(multi, github, transport, &strategy, &url, &clone_path)?;
+
+    // The fork's default branch is often far behind, so start from upstream
+    let upstream = ensure_remote(&git_repo, "upstream", transport, owner, repo)?;
+    status_pb.set_message(format!("Fetching {upstream}/{base_branch}..."));
+    let base = git::fetch_branch(
+        &git_repo,
+        &upstream,
+        base_branch,
+        transport,
+        github.token(),
+        &strategy,
+    )?;
+
+    Ok(Checkout {
+        git_repo,
+        path,
+        fork_remote: "origin".to_string(),
+        transport,
+        base,
+        strategy,
+        worktree,
+    })
+}
+
+/// The clone at `--repo-path`, or the one we're inside of if it belongs to the repository
+fn local_clone(
+    options: &StartOptions,
+    owner: &str,
+    repo: &str,
+    fork_owner: &str,
+) -> eyre::Result<Option<Repository>> {
+    let belongs = |git_repo: &Repository| -> eyre::Result<bool> {
+        Ok(git::find_remote(git_repo, owner, repo)?.is_some()
+            || git::find_remote(git_repo, fork_owner, repo)?.is_some())
+    };
+
+    if let Some(path) = &options.repo_path {
+        let git_repo = Repository::open(path)
+            .wrap_err_with(|| format!("failed to open repository at {}", path.display()))?;
+        if !belongs(&git_repo)? {
+            bail!(
+                "{} has no remote for {owner}/{repo} or your fork",
+                path.display()
+            );
+        }
+        return Ok(Some(git_repo));
+    }
+
+    let Ok(git_repo) = Repository::discover(".") else {
+        return Ok(None);
+    };
+    Ok(belongs(&git_repo)?.then_some(git_repo))
+}
+
+/// Add whichever of the upstream and fork remotes is missing, and fetch the upstream base
+/// branch to start from
+fn use_local_clone(
+    git_repo: Repository,
+    github: &GitHub,
+    (transport, strategy): (Transport, CloneConfig),
+    (owner, repo): (&str, &str),
+    fork_owner: &str,
+    base_branch: &str,
+) -> eyre::Result<Checkout> {
+    let path = git_repo
+        .workdir()
+        .ok_or_else(|| eyre::eyre!("can't work on a bounty in a bare repository"))?
+        .to_path_buf();
 
-    let head = git_repo.head()?.peel_to_commit()?;
-    git_repo.branch(&branch_name, &head, false)?;
-
-    // Create empty commit
-    let sig = git_repo.signature()?;
-    let tree_id = head.tree_id();
-    let tree = git_repo.find_tree(tree_id)?;
-
-    git_repo.commit(
-        Some(&format!("refs/heads/{branch_name}")),
-        &sig,
-        &sig,
-        "Start work on bounty",
-        &tree,
-        &[&head],
+    let upstream = ensure_remote(&git_repo, "upstream", transport, owner, repo)?;
+    let fork_remote = ensure_remote(&git_repo, fork_owner, transport, fork_owner, repo)?;
+
+    let base = git::fetch_branch(
+        &git_repo,
+        &upstream,
+        base_branch,
+        transport,
+        github.token(),
+        &CloneConfig::default(),
     )?;
 
-    // Push the branch
-    status_pb.set_message("Pushing branch...");
-    let mut remote = git_repo.find_remote("origin")?;
+    Ok(Checkout {
+        git_repo,
+        path,
+        fork_remote,
+        transport,
+        base,
+        strategy,
+        worktree: false,
+    })
+}
+
+/// Name of the remote for `owner/repo`, adding it as `name` if there isn't one
+fn ensure_remote(
+    git_repo: &Repository,
+    name: &str,
+    transport: Transport,
+    owner: &str,
+    repo: &str,
+) -> eyre::Result<String> {
+    if let Some(existing) = git::find_remote(git_repo, owner, repo)? {
+        return Ok(existing);
+    }
+    git_repo
+        .remote(name, &git::remote_url(transport, owner, repo))
+        .wrap_err_with(|| format!("failed to add remote {name} for {owner}/{repo}"))?;
+    Ok(name.to_string())
+}
+
+/// Reuse the checkout from an earlier run, or clone the fork into it
+fn open_or_clone(
+    multi: &MultiProgress,
+    github: &GitHub,
+    transport: Transport,
+    strategy: &CloneConfig,
+    url: &str,
+    checkout: &Path,
+) -> eyre::Result<Repository> {
+    if checkout.join(".git").exists() {
+        return Repository::open(checkout).wrap_err("failed to open existing checkout");
+    }
+
+    let progress = multi.add(ProgressBar::new(0));
+    progress.set_style(
+        ProgressStyle::with_template("  [{bar:30.green/dim}] {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("=> "),
+    );
+    let on_progress = |phase: &str, done: usize, total: usize| {
+        progress.set_message(phase.to_string());
+        progress.set_length(total as u64);
+        progress.set_position(done as u64);
+    };
+
+    let cloned = git::clone(
+        url,
+        checkout,
+        transport,
+        github.token(),
+        strategy,
+        &on_progress,
+    );
+    progress.finish_and_clear();
+    cloned
+}
+
+fn push_branch(
+    github: &GitHub,
+    transport: Transport,
+    git_repo: &Repository,
+    remote_name: &str,
+    branch_name: &str,
+) -> eyre::Result<()> {
+    let rejection = RefCell::new(None);
+    let mut remote = git_repo.find_remote(remote_name)?;
+    let transport = git::transport_for_url(remote.url().unwrap_or_default(), transport);
+    let mut callbacks = git::remote_callbacks(transport, github.token());
+    callbacks.push_update_reference(|_refname, status| {
+        *rejection.borrow_mut() = status.map(str::to_string);
+        Ok(())
+    });
     let mut push_options = PushOptions::new();
     push_options.remote_callbacks(callbacks);
 
+    // Never forced, so commits on the fork we haven't seen can't be lost
     remote.push(
         &[&format!(
-            "+refs/heads/{branch_name}:refs/heads/{branch_name}"
+            "refs/heads/{branch_name}:refs/heads/{branch_name}"
         )],
         Some(&mut push_options),
     )?;
+    drop(push_options);
 
-    // Get repository info to find default branch
-    status_pb.set_message("Getting repository info...");
-    let repo_info = github.get_repo_info(owner, repo).await?;
+    if let Some(reason) = rejection.into_inner() {
+        bail!("{remote_name} rejected {branch_name}: {reason}");
+    }
+    Ok(())
+}
+
+/// Check out the bounty branch
+fn checkout_branch(git_repo: &Repository, branch_name: &str) -> eyre::Result<()> {
+    if git::needs_git_cli(git_repo) {
+        return git::switch(git_repo, branch_name);
+    }
+
+    let refname = format!("refs/heads/{branch_name}");
+    let tree = git_repo.revparse_single(&refname)?;
+    // Nothing to update when the trees match
+    let head_tree = git_repo.head()?.peel_to_tree()?.id();
+    if tree.peel_to_tree()?.id() != head_tree {
+        git_repo.checkout_tree(&tree, Some(git2::build::CheckoutBuilder::new().safe()))?;
+    }
+    git_repo.set_head(&refname)?;
+    Ok(())
+}
+
+/// Run the configured post-checkout hooks and the project's trusted ones, returning the
+/// commands that failed
+///
+/// Hooks come after the branch is pushed, so a problem with them is only recorded in the
+/// returned list, never stopping the pull request from being opened.
+fn run_hooks(
+    multi: &MultiProgress,
+    status_pb: &ProgressBar,
+    config: &Config,
+    (owner, repo): (&str, &str),
+    checkout: &Path,
+) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut skip = |what: &str, err: &eyre::Report| {
+        let warning = format!("skipping {what}: {err:#}");
+        status_pb.suspend(|| super::print_warning(&warning));
+        // A comment, so the list can still be pasted into a shell
+        problems.push(format!("# {warning}"));
+    };
+
+    let mut commands = config.hooks_for(owner, repo).post_checkout;
+    match project_hooks(status_pb, owner, repo, checkout) {
+        Ok(project_commands) => commands.extend(project_commands),
+        Err(err) => skip(&format!("{} hooks", ProjectConfig::FILE_NAME), &err),
+    }
+
+    if commands.is_empty() {
+        return problems;
+    }
+    status_pb.set_message("Running post-checkout hooks...");
+    let failed = hooks::run(&commands, checkout, multi);
+    for (command, err) in &failed {
+        let warning = format!("hook `{command}` failed: {err:#}");
+        status_pb.suspend(|| super::print_warning(&warning));
+    }
+    problems.extend(failed.into_iter().map(|(command, _)| command));
+    problems
+}
+
+/// Post-checkout hooks from the checkout's `.bounty.toml`, if the user trusts them
+fn project_hooks(
+    status_pb: &ProgressBar,
+    owner: &str,
+    repo: &str,
+    checkout: &Path,
+) -> eyre::Result<Vec<String>> {
+    let project_file = checkout.join(ProjectConfig::FILE_NAME);
+    if !project_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let project = ProjectConfig::parse(&std::fs::read_to_string(project_file)?)?;
+    let project_commands = project.hooks.post_checkout;
+    let repo_ref = format!("{owner}/{repo}");
+    if project_commands.is_empty()
+        || !hooks::confirm_project_hooks(&repo_ref, &project_commands, status_pb)?
+    {
+        return Ok(Vec::new());
+    }
+    Ok(project_commands)
+}
+
+/// Make the bounty branch track the fork's branch of the same name
+fn set_upstream(git_repo: &Repository, fork_remote: &str, branch_name: &str) -> eyre::Result<()> {
+    let refname = format!("refs/heads/{branch_name}");
+    let mut config = git_repo.config()?;
+    config.set_str(&format!("branch.{branch_name}.remote"), fork_remote)?;
+    config.set_str(&format!("branch.{branch_name}.merge"), &refname)?;
+    Ok(())
+}
+
+/// Point branch `refname` at `tip`, creating it if needed
+///
+/// A branch checked out in the clone, which may be the user's own, is only fast-forwarded, as
+/// moving its ref would leave the files out of step with HEAD.
+fn move_branch(git_repo: &Repository, refname: &str, tip: Oid) -> eyre::Result<()> {
+    let Ok(current) = git_repo.refname_to_id(refname) else {
+        git_repo.reference(refname, tip, false, "bounty: start branch")?;
+        return Ok(());
+    };
+    if current == tip {
+        return Ok(());
+    }
+
+    let checked_out = git_repo
+        .head()
+        .is_ok_and(|head| head.name() == Some(refname));
+    if !checked_out {
+        git_repo.reference(refname, tip, true, "bounty: start branch")?;
+        return Ok(());
+    }
+    let branch = refname.trim_start_matches("refs/heads/");
+    if !git_repo.graph_descendant_of(tip, current)? {
+        bail!("{branch} is checked out and can't be fast-forwarded to {tip}; switch to another branch first");
+    }
+    git::fast_forward(git_repo, tip).wrap_err_with(|| format!("failed to update {branch}"))
+}
+
+/// Install a pre-push hook that opens the draft PR in the background once the branch is pushed
+///
+/// The hook finds the bounty from the directory it runs in and the branches being pushed, which
+/// git passes on stdin, so worktrees and bounties sharing a clone can share it.
+fn install_pr_hook(checkout: &Path) -> eyre::Result<()> {
+    let hook = git::hooks_dir(checkout)?.join("pre-push");
+    if is_pr_hook(&hook) {
+        return Ok(());
+    }
+    if hook.exists() {
+        bail!(
+            "{} already exists; run `bounty pr` after your first push instead",
+            hook.display()
+        );
+    }
+
+    let bounty_exe = std::env::current_exe()?;
+    let script = format!(
+        "#!/bin/sh\n{PR_HOOK_MARKER}\n\
+         refs=$(cut -d ' ' -f 1)\n\
+         \"{}\" pr --after-push $refs >/dev/null 2>&1 &\n",
+        bounty_exe.display(),
+    );
+    std::fs::create_dir_all(hook.parent().unwrap())?;
+    std::fs::write(&hook, script)?;
+    make_executable(&hook)
+}
+
+fn is_pr_hook(hook: &Path) -> bool {
+    std::fs::read_to_string(hook).is_ok_and(|script| script.contains(PR_HOOK_MARKER))
+}
 
-    let default_branch = repo_info
-        .default_branch
-        .unwrap_or_else(|| "main".to_string());
+/// Remove the hook installed by [`install_pr_hook`] once no bounty sharing it is waiting for a
+/// push, leaving any other pre-push hook alone
+pub(super) fn remove_pr_hook(state: &State, checkout: &Path) -> eyre::Result<()> {
+    let hooks = git::hooks_dir(checkout)?;
+    let still_waiting = state
+        .bounties
+        .iter()
+        .filter(|bounty| bounty.pr_on_push && bounty.pr_number.is_none())
+        .filter_map(|bounty| bounty.checkout.as_deref())
+        .any(|other| git::hooks_dir(other).is_ok_and(|other_hooks| other_hooks == hooks));
+
+    let hook = hooks.join("pre-push");
+    if !still_waiting && is_pr_hook(&hook) {
+        std::fs::remove_file(hook)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> eyre::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> eyre::Result<()> {
+    Ok(())
+}
+
+/// Find or create the bounty's draft pull request, then claim the issue if not done yet
+pub(super) async fn open_draft_pr(
+    github: &GitHub,
+    config: &Config,
+    bounty: &mut TrackedBounty,
+    templates: &Templates,
+    user: &str,
+    status_pb: &ProgressBar,
+) -> eyre::Result<()> {
+    let TrackedBounty {
+        owner,
+        repo,
+        fork_owner,
+        branch,
+        base,
+        ..
+    } = bounty.clone();
 
     // Check if PR already exists
     status_pb.set_message("Checking for existing pull requests...");
-    let head = format!("{fork_owner}:{branch_name}");
-    let pr = if let Some(existing_pr) = github.find_existing_pr(owner, repo, &head).await? {
+    let head = format!("{fork_owner}:{branch}");
+    let pr = if let Some(existing_pr) = github.find_existing_pr(&owner, &repo, &head).await? {
         status_pb.set_message("Found existing pull request");
         existing_pr
     } else {
+        let body = pr_body(github, &owner, &repo, templates, status_pb).await?;
+        let base = if base.is_empty() {
+            // Tracked by an older version, which always targeted the default branch
+            github
+                .get_repo_info(&owner, &repo)
+                .await?
+                .default_branch
+                .unwrap_or_else(|| "main".to_string())
+        } else {
+            base
+        };
+
         // Create draft PR
         status_pb.set_message("Creating draft pull request...");
         github
-            .create_draft_pr(
-                owner,
-                repo,
-                format!("Resolves: #{issue_number}"),
-                head,
-                default_branch,
-                format!(
-                    "This pull request resolves issue #{issue_number}.\n\n\
-                    Work in progress 🚧\n\n\
-                    ## Bounty Guidelines:\n\
-                    - Please post your progress at least once per day\n\
-                    - This PR will remain in draft status until work is complete\n\
-                    - Only mark as ready for review when you have finished your work\n\
-                    - You may be unassigned from this issue if there are no pushes for several days\n\
-                    \n\
-                    ## Community\n\
-                    [Join Our Discord](https://discord.gg/jcQpX6jW8u) to connect with other bounty hunters and give feedback.\n\
-                    \n\
-                    Happy coding! 🎉"
-                ),
-            )
+            .create_draft_pr(&owner, &repo, templates.pr_title.clone(), head, base, body)
             .await?
     };
 
-    status_pb.finish_with_message(format!("✨ Ready to work on issue #{issue_number}"));
+    let pr_url = pr.html_url.map(|url| url.to_string()).unwrap_or_default();
+    bounty.pr_number = Some(pr.number);
+    bounty.pr_on_push = false;
+    bounty.pr_state = Some(if pr.draft.unwrap_or(false) {
+        PrState::Draft
+    } else {
+        PrState::Ready
+    });
+    bounty.pr_url = Some(pr_url.clone());
+
+    if bounty.claim_comment_id.is_none() && !bounty.self_assigned {
+        status_pb.set_message("Claiming issue...");
+        claim_issue(github, config, bounty, &pr_url, user, status_pb).await?;
+    }
+    Ok(())
+}
+
+/// Post the configured claim comment and/or self-assign, recording what was done
+async fn claim_issue(
+    github: &GitHub,
+    config: &Config,
+    bounty: &mut TrackedBounty,
+    pr_url: &str,
+    user: &str,
+    status_pb: &ProgressBar,
+) -> eyre::Result<()> {
+    let TrackedBounty {
+        owner,
+        repo,
+        issue_number,
+        ..
+    } = bounty.clone();
+    let claim = config.claim_for(&owner, &repo);
+
+    if let Some(comment) = &claim.comment {
+        let body = render(
+            comment,
+            &[
+                ("owner", owner.as_str()),
+                ("repo", repo.as_str()),
+                ("issue_number", &issue_number.to_string()),
+                ("pr_url", pr_url),
+            ],
+        );
+        let comment_id = github
+            .create_comment(&owner, &repo, issue_number, &body)
+            .await?;
+        bounty.claim_comment_id = Some(comment_id);
+    }
+
+    if claim.self_assign {
+        bounty.self_assigned = github.assign(&owner, &repo, issue_number, user).await?;
+        if !bounty.self_assigned {
+            let warning = format!(
+                "couldn't assign you to the issue; you may lack permission in {owner}/{repo}"
+            );
+            status_pb.suspend(|| super::print_warning(&warning));
+        }
+    }
 
-    // Print final status in a clean way
-    println!("\n🔗 Issue: https://github.com/{owner}/{repo}/issues/{issue_number}");
-    println!("🌿 Branch: https://github.com/{fork_owner}/{repo}/tree/{branch_name}");
-    println!("📝 Pull Request: {}", pr.html_url.unwrap());
+    Ok(())
+}
+
+/// Emails commits of ours may be authored with
+fn my_emails(
+    git_repo: &Repository,
+    git_settings: &GitConfig,
+    profile: &UserProfile,
+) -> Vec<String> {
+    let (_, git_email) = git_repo
+        .config()
+        .map(|git_config| IdentitySources::git_config(&git_config))
+        .unwrap_or_default();
+    let (_, noreply) = IdentitySources::github(profile);
+    [
+        git_settings.email.clone(),
+        git_email,
+        noreply,
+        profile.email.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|email| !email.is_empty())
+    .collect()
+}
+
+/// Commit author and signing settings from the bounty config, git config and GitHub profile
+fn commit_settings(
+    git_repo: &Repository,
+    git_settings: &GitConfig,
+    profile: &UserProfile,
+) -> eyre::Result<(Identity, Option<Signing>)> {
+    let git_config = git_repo.config()?;
+
+    let identity = IdentitySources {
+        config: (git_settings.name.clone(), git_settings.email.clone()),
+        git: IdentitySources::git_config(&git_config),
+        github: IdentitySources::github(profile),
+    }
+    .resolve()?;
+    let signing = Signing::from_git_config(&git_config, git_settings.sign)?;
+
+    Ok((identity, signing))
+}
+
+/// The PR body: the upstream pull request template (if wanted) followed by the bounty section
+async fn pr_body(
+    github: &GitHub,
+    owner: &str,
+    repo: &str,
+    templates: &Templates,
+    status_pb: &ProgressBar,
+) -> eyre::Result<String> {
+    if !templates.use_repo_pr_template {
+        return Ok(templates.pr_body.clone());
+    }
+
+    status_pb.set_message("Looking for pull request templates...");
+    // The branch is already pushed, so a failed lookup shouldn't stop the draft PR
+    let repo_templates = match pr_template::fetch(github, owner, repo).await {
+        Ok(repo_templates) => repo_templates,
+        Err(err) => {
+            let warning = format!("couldn't look for pull request templates: {err:#}");
+            status_pb.suspend(|| super::print_warning(&warning));
+            return Ok(templates.pr_body.clone());
+        }
+    };
+    let chosen = status_pb.suspend(|| pr_template::choose(repo_templates))?;
+
+    Ok(pr_template::merge(chosen.as_ref(), &templates.pr_body))
+}
+
+/// Templates from the config, overridden by the upstream repository's `.bounty.toml`
+///
+/// That file belongs to upstream, so one this version can't read is ignored with a warning.
+pub(super) async fn resolve_templates(
+    github: &GitHub,
+    config: &Config,
+    owner: &str,
+    repo: &str,
+) -> eyre::Result<Templates> {
+    let project = github
+        .get_file(owner, repo, ProjectConfig::FILE_NAME)
+        .await?
+        .and_then(|contents| match ProjectConfig::parse(&contents) {
+            Ok(project) => Some(project),
+            Err(err) => {
+                warn!(
+                    "ignoring {owner}/{repo}'s {}: {err:#}",
+                    ProjectConfig::FILE_NAME
+                );
+                None
+            }
+        });
+
+    Ok(config.templates_for(owner, repo, project.as_ref()))
+}
+
+/// Warn about (or refuse) issues that are closed, locked, assigned or already being worked on
+async fn check_eligibility(
+    github: &GitHub,
+    issue: &Issue,
+    owner: &str,
+    repo: &str,
+    user: &str,
+    options: &StartOptions,
+    status_pb: &ProgressBar,
+) -> eyre::Result<()> {
+    let issue_number = issue.number;
+    let linked_prs = github
+        .linked_pull_requests(owner, repo, issue_number)
+        .await?;
+
+    let problems = crate::eligibility::check(issue, &linked_prs, user);
+    debug!(?problems, "eligibility checked");
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    status_pb.suspend(|| {
+        for problem in &problems {
+            super::print_warning(&problem.to_string());
+        }
+    });
 
+    if !options.force {
+        bail!("issue #{issue_number} doesn't look available; rerun with --force to start anyway");
+    }
     Ok(())
 }
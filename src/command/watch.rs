@@ -0,0 +1,107 @@
+use crate::config::WatchConfig;
+use crate::notify::notify_all;
+use crate::sources::{configured_sources, fetch_all, Fetched};
+use crate::watch::{matches_any, SeenQuests};
+use crate::{Config, GitHub};
+use eyre::Result;
+use owo_colors::OwoColorize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+pub async fn handle(
+    interval: Option<u64>,
+    labels: Vec<String>,
+    once: bool,
+    token: Option<&str>,
+) -> Result<()> {
+    let Config {
+        watch, mut sources, ..
+    } = Config::load()?;
+
+    let interval = Duration::from_secs(interval.unwrap_or(watch.interval_secs).max(1));
+    if !labels.is_empty() {
+        sources.github_labels = labels;
+    }
+
+    let github = token.map(GitHub::new).transpose()?;
+    let sources = configured_sources(&sources, github);
+    let mut seen = SeenQuests::load()?;
+
+    super::print_step(&format!(
+        "Watching for new quests every {}s (Ctrl-C to stop)",
+        interval.as_secs()
+    ));
+
+    loop {
+        poll(&mut seen, fetch_all(&sources).await, &watch).await?;
+
+        if once {
+            return Ok(());
+        }
+
+        tokio::select! {
+            () = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Remember a poll's quests, notifying about new ones
+///
+/// A poll where every source failed says nothing about which quests exist, so it's skipped.
+async fn poll(seen: &mut SeenQuests, fetched: Fetched, watch: &WatchConfig) -> Result<()> {
+    if fetched.all_failed() {
+        super::print_warning("every quest source failed; trying again on the next poll");
+        return Ok(());
+    }
+
+    crate::quest_cache::store(&fetched.quests);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let observed = seen.observe(fetched, now);
+    if observed.seeded > 0 {
+        // Existing quests of a source seen for the first time aren't news
+        let tracking = format!("Tracking {} existing quests", observed.seeded);
+        println!("  {}", tracking.dimmed());
+    }
+    debug!(count = observed.new.len(), "new quests");
+
+    for quest in observed
+        .new
+        .iter()
+        .filter(|quest| matches_any(&watch.filters, quest))
+    {
+        notify_all(&watch.notify, quest).await;
+    }
+    seen.save()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_poll_does_not_seed() {
+        let mut seen = SeenQuests::default();
+        let fetched = Fetched {
+            failed: vec!["first".to_string(), "second".to_string()],
+            ..Fetched::default()
+        };
+
+        poll(&mut seen, fetched, &WatchConfig::default())
+            .await
+            .unwrap();
+        // Still seeded, rather than notified, when the source answers
+        let answered = Fetched {
+            quests: vec![crate::QuestIssue {
+                title: "Existing".to_string(),
+                issue: crate::RepoIssue::parse("acme/rocket#1").unwrap(),
+                source: "first".to_string(),
+            }],
+            answered: vec!["first".to_string()],
+            ..Fetched::default()
+        };
+        assert_eq!(seen.observe(answered, 0).seeded, 1);
+    }
+}
@@ -1,36 +1,419 @@
-use eyre::Result;
+use crate::notify::Notifier;
+use crate::template::Templates;
+use crate::watch::QuestFilter;
+use eyre::{Result, WrapErr};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
+pub mod layers;
+mod migrate;
+mod paths;
+
+pub use layers::Layers;
+pub use paths::{
+    cache_dir, cache_file, config_dir, config_path, state_dir, state_file, xdg_dir, CONFIG_ENV,
+};
+
 #[derive(Debug, Deserialize, Serialize, Default)]
 pub struct Config {
+    #[serde(default)]
+    pub version: ConfigVersion,
     pub github_token: Option<String>,
+    #[serde(default)]
     pub has_completed_first_time_setup: bool,
+    #[serde(default)]
+    pub watch: WatchConfig,
+    #[serde(default)]
+    pub sources: SourcesConfig,
+    #[serde(default)]
+    pub claim: ClaimConfig,
+    #[serde(default)]
+    pub templates: TemplatesConfig,
+    #[serde(default)]
+    pub git: GitConfig,
+    #[serde(default)]
+    pub solve: SolveConfig,
+    #[serde(default, rename = "clone")]
+    pub clone_strategy: CloneConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// Where bounty checkouts are kept (defaults to `~/bounties`)
+    pub workspace_dir: Option<PathBuf>,
+    /// Overrides keyed by organization (`owner`) or repository (`owner/repo`)
+    #[serde(default)]
+    pub repos: BTreeMap<String, RepoConfig>,
+}
+
+/// Version of the config file format; older files are upgraded when loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ConfigVersion(pub u32);
+
+impl Default for ConfigVersion {
+    fn default() -> Self {
+        Self(migrate::CURRENT_VERSION)
+    }
+}
+
+/// How bounty interacts with git
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit author name, instead of git's `user.name`
+    pub name: Option<String>,
+    /// Commit author email, instead of git's `user.email`
+    pub email: Option<String>,
+    /// Sign commits, instead of following git's `commit.gpgsign`
+    pub sign: Option<bool>,
+    /// How to authenticate clones and pushes
+    pub transport: Transport,
+}
+
+/// How git talks to GitHub
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// HTTPS authenticated with the bounty OAuth token (never written to the remote URL)
+    #[default]
+    Token,
+    /// SSH URLs, authenticated with ssh-agent or your `~/.ssh` keys
+    Ssh,
+    /// HTTPS authenticated by your git credential helper
+    CredentialHelper,
+}
+
+/// Crash reporting to Sentry
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Whether crash reports are sent (unset until you've been asked)
+    pub enabled: Option<bool>,
+}
+
+impl TelemetryConfig {
+    /// Crash reports are only sent once you've agreed to them
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled == Some(true)
+    }
+}
+
+/// Commands run in a new bounty checkout, e.g. `pnpm install`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HooksConfig {
+    /// Run in the checkout once it's set up, before the draft PR is opened
+    pub post_checkout: Vec<String>,
+}
+
+/// How much of a repository to download, for repositories too big to clone in full
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CloneConfig {
+    /// Only fetch this many commits of history
+    pub depth: Option<u32>,
+    /// Partial clone filter, e.g. `blob:none` to fetch file contents on demand
+    pub filter: Option<String>,
+    /// Only check out these directories (sparse checkout in cone mode)
+    pub sparse: Vec<String>,
+}
+
+impl CloneConfig {
+    /// libgit2 can't do partial or sparse clones, so those are left to the git CLI
+    #[must_use]
+    pub fn needs_git_cli(&self) -> bool {
+        self.filter.is_some() || !self.sparse.is_empty()
+    }
+}
+
+/// What `bounty solve` sets up besides the fork and branch
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SolveConfig {
+    /// Create an empty "start work" commit so a draft PR can be opened right away
+    pub commit: bool,
+    /// When to open the draft pull request
+    pub pr: PrMode,
+    /// Keep one clone per repository and check each bounty out in its own git worktree
+    pub worktrees: bool,
+    /// Update the fork with GitHub's "Sync fork" before branching
+    pub sync_fork: bool,
+    /// What to do once the bounty is set up
+    pub after: Vec<PostStartAction>,
+    /// Editor command for the `editor` action, e.g. `code` or `zed` (defaults to `$VISUAL`/`$EDITOR`)
+    pub editor: Option<String>,
+}
+
+impl Default for SolveConfig {
+    fn default() -> Self {
+        Self {
+            commit: true,
+            pr: PrMode::Now,
+            worktrees: false,
+            sync_fork: false,
+            after: Vec::new(),
+            editor: None,
+        }
+    }
+}
+
+/// Something to do once `bounty solve` has set up a bounty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStartAction {
+    /// Open the checkout in your editor
+    Editor,
+    /// Start a shell in the checkout, with the bounty described in `BOUNTY_*` variables
+    Shell,
+    /// Open the issue and pull request in your browser
+    Browser,
+}
+
+/// When `bounty solve` opens the draft pull request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrMode {
+    /// Immediately, on top of the bootstrap commit
+    #[default]
+    Now,
+    /// Only when running `bounty pr`
+    Manual,
+    /// Automatically, the first time the branch is pushed
+    OnFirstPush,
+}
+
+/// Settings that can be overridden for a single organization or repository
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub claim: Option<ClaimConfig>,
+    pub templates: TemplatesConfig,
+    pub transport: Option<Transport>,
+    #[serde(rename = "clone")]
+    pub clone_strategy: Option<CloneConfig>,
+    pub hooks: Option<HooksConfig>,
+}
+
+/// Settings maintainers can commit to their repository as `.bounty.toml`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ProjectConfig {
+    pub templates: TemplatesConfig,
+    /// Only run once the user trusts them
+    pub hooks: HooksConfig,
+}
+
+impl ProjectConfig {
+    /// Path of the project config file, relative to the repository root
+    pub const FILE_NAME: &'static str = ".bounty.toml";
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        toml::from_str(contents).wrap_err_with(|| format!("invalid {}", Self::FILE_NAME))
+    }
+}
+
+/// Templates for the branch, commit and pull request created by `bounty solve`
+///
+/// Unset fields fall back to less specific settings. See [`crate::template::Templates`]
+/// for the available placeholders.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TemplatesConfig {
+    pub branch: Option<String>,
+    pub commit_message: Option<String>,
+    pub pr_title: Option<String>,
+    pub pr_body: Option<String>,
+    /// Start the PR body with the upstream repository's pull request template
+    pub use_repo_pr_template: Option<bool>,
+}
+
+impl TemplatesConfig {
+    /// Fill the fields left unset here from `fallback`
+    #[must_use]
+    fn or(self, fallback: &Self) -> Self {
+        Self {
+            branch: self.branch.or_else(|| fallback.branch.clone()),
+            commit_message: self
+                .commit_message
+                .or_else(|| fallback.commit_message.clone()),
+            pr_title: self.pr_title.or_else(|| fallback.pr_title.clone()),
+            pr_body: self.pr_body.or_else(|| fallback.pr_body.clone()),
+            use_repo_pr_template: self.use_repo_pr_template.or(fallback.use_repo_pr_template),
+        }
+    }
+}
+
+/// How to claim an issue once its draft PR exists
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ClaimConfig {
+    /// Comment to post on the issue, e.g. `/attempt #{issue_number}`
+    ///
+    /// Supports `{owner}`, `{repo}`, `{issue_number}` and `{pr_url}` placeholders.
+    pub comment: Option<String>,
+    /// Assign yourself to the issue (only works with triage access to the repository)
+    pub self_assign: bool,
+}
+
+/// Default number of seconds between `bounty watch` polls
+const DEFAULT_WATCH_INTERVAL_SECS: u64 = 300;
+
+/// Settings for `bounty watch`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Seconds to wait between polls
+    pub interval_secs: u64,
+    /// Only notify about quests matching at least one filter (all quests if empty)
+    pub filters: Vec<QuestFilter>,
+    /// How to deliver notifications
+    pub notify: Vec<Notifier>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: DEFAULT_WATCH_INTERVAL_SECS,
+            filters: Vec::new(),
+            notify: vec![Notifier::Bell, Notifier::Desktop],
+        }
+    }
+}
+
+/// Where quests are discovered, used by both the quest picker and `bounty watch`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourcesConfig {
+    /// Offer quests from BountyBot
+    pub bountybot: bool,
+    /// GitHub labels that mark an issue as a bounty, e.g. `bounty` or `💎 Bounty`
+    pub github_labels: Vec<String>,
+    /// Only search labeled issues in repositories you star or watch
+    pub github_followed_only: bool,
+    /// Public JSON feeds of third-party bounty platforms
+    pub feeds: Vec<FeedConfig>,
+}
+
+impl Default for SourcesConfig {
+    fn default() -> Self {
+        Self {
+            bountybot: true,
+            github_labels: Vec::new(),
+            github_followed_only: true,
+            feeds: Vec::new(),
+        }
+    }
+}
+
+/// A bounty platform's JSON feed, described with JSON pointers into the document
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FeedConfig {
+    /// Name shown next to quests from this feed
+    pub name: String,
+    pub url: String,
+    /// Pointer to the array of bounties (the document root by default)
+    #[serde(default)]
+    pub items: String,
+    /// Pointer, relative to each bounty, to its GitHub issue URL
+    #[serde(default = "default_url_field")]
+    pub url_field: String,
+    /// Pointer, relative to each bounty, to its title
+    #[serde(default = "default_title_field")]
+    pub title_field: String,
+}
+
+fn default_url_field() -> String {
+    "/url".to_string()
+}
+
+fn default_title_field() -> String {
+    "/title".to_string()
 }
 
 impl Config {
+    /// Load the config from the system and user config files and `BOUNTY_*` variables
     pub fn load() -> Result<Self> {
-        let config_path = config_path()?;
+        Layers::load()?.config()
+    }
 
-        // If the config file exists, try to read it
-        let config = if config_path.exists() {
-            let contents = std::fs::read_to_string(&config_path)?;
-            toml::from_str(&contents)?
-        } else {
-            Self::default()
-        };
+    /// Save the settings that belong in the user config file
+    pub fn save(&self) -> Result<()> {
+        layers::write_user(&Layers::load()?.user_table_for(self)?)
+    }
 
-        Ok(config)
+    /// Repository overrides that apply to `owner/repo`, most specific first
+    fn overrides<'a>(&'a self, owner: &str, repo: &str) -> impl Iterator<Item = &'a RepoConfig> {
+        [format!("{owner}/{repo}"), owner.to_string()]
+            .into_iter()
+            .filter_map(|key| self.repos.get(&key))
     }
 
-    pub fn save(&self) -> Result<()> {
-        let config_path = config_path()?;
-        let config_dir = config_path.parent().unwrap();
-        std::fs::create_dir_all(config_dir)?;
+    /// Claim settings for a repository, falling back to the global settings
+    #[must_use]
+    pub fn claim_for(&self, owner: &str, repo: &str) -> ClaimConfig {
+        self.overrides(owner, repo)
+            .find_map(|overrides| overrides.claim.clone())
+            .unwrap_or_else(|| self.claim.clone())
+    }
 
-        let contents = toml::to_string_pretty(self)?;
-        std::fs::write(config_path, contents)?;
-        Ok(())
+    /// Hooks for a repository, falling back to the global `[hooks]`
+    #[must_use]
+    pub fn hooks_for(&self, owner: &str, repo: &str) -> HooksConfig {
+        self.overrides(owner, repo)
+            .find_map(|overrides| overrides.hooks.clone())
+            .unwrap_or_else(|| self.hooks.clone())
+    }
+
+    /// Clone strategy for a repository, falling back to the global `[clone]` settings
+    #[must_use]
+    pub fn clone_strategy_for(&self, owner: &str, repo: &str) -> CloneConfig {
+        self.overrides(owner, repo)
+            .find_map(|overrides| overrides.clone_strategy.clone())
+            .unwrap_or_else(|| self.clone_strategy.clone())
+    }
+
+    /// Git transport for a repository, falling back to `git.transport`
+    #[must_use]
+    pub fn transport_for(&self, owner: &str, repo: &str) -> Transport {
+        self.overrides(owner, repo)
+            .find_map(|overrides| overrides.transport)
+            .unwrap_or(self.git.transport)
+    }
+
+    /// Templates for a repository: its `.bounty.toml`, then repository, organization and
+    /// global settings, then the built-in defaults
+    #[must_use]
+    pub fn templates_for(
+        &self,
+        owner: &str,
+        repo: &str,
+        project: Option<&ProjectConfig>,
+    ) -> Templates {
+        let configured = self
+            .overrides(owner, repo)
+            .map(|overrides| &overrides.templates)
+            .chain([&self.templates])
+            .fold(
+                project
+                    .map(|project| project.templates.clone())
+                    .unwrap_or_default(),
+                TemplatesConfig::or,
+            );
+
+        Templates::from_config(configured)
+    }
+
+    /// Directory holding bounty checkouts
+    pub fn workspace_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.workspace_dir {
+            return Ok(dir.clone());
+        }
+        let home =
+            dirs::home_dir().ok_or_else(|| eyre::eyre!("Could not determine home directory"))?;
+        Ok(home.join("bounties"))
     }
 
     /// Try to get the GitHub token from config file or environment
@@ -67,7 +450,75 @@ impl Config {
     }
 }
 
-fn config_path() -> Result<PathBuf> {
-    let home = dirs::home_dir().ok_or_else(|| eyre::eyre!("Could not determine home directory"))?;
-    Ok(home.join(".config").join("bounty").join("config.toml"))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim_overrides_prefer_most_specific() {
+        let config: Config = toml::from_str(
+            r#"
+            has_completed_first_time_setup = true
+
+            [claim]
+            comment = "global"
+
+            [repos.acme.claim]
+            comment = "org"
+
+            [repos."acme/rocket".claim]
+            self_assign = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.claim_for("other", "repo").comment.as_deref(),
+            Some("global")
+        );
+        assert_eq!(
+            config.claim_for("acme", "anvil").comment.as_deref(),
+            Some("org")
+        );
+
+        let repo = config.claim_for("acme", "rocket");
+        assert_eq!(repo.comment, None);
+        assert!(repo.self_assign);
+    }
+
+    #[test]
+    fn test_templates_layering() {
+        let config: Config = toml::from_str(
+            r#"
+            has_completed_first_time_setup = true
+
+            [templates]
+            branch = "bounty/{issue_number}"
+            pr_title = "global title"
+
+            [repos.acme.templates]
+            pr_title = "org title"
+            "#,
+        )
+        .unwrap();
+        let project = ProjectConfig::parse(
+            r#"
+            [templates]
+            branch = "fix/{issue_number}-{slug}"
+            "#,
+        )
+        .unwrap();
+
+        let templates = config.templates_for("acme", "rocket", Some(&project));
+        assert_eq!(templates.branch, "fix/{issue_number}-{slug}");
+        assert_eq!(templates.pr_title, "org title");
+
+        let templates = config.templates_for("other", "repo", None);
+        assert_eq!(templates.branch, "bounty/{issue_number}");
+        assert_eq!(templates.pr_title, "global title");
+        assert_eq!(
+            templates.commit_message,
+            Templates::default().commit_message
+        );
+    }
 }
@@ -0,0 +1,529 @@
+use super::{config_path, migrate, Config};
+use eyre::{bail, Result, WrapErr};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Prefix of environment variables overriding config keys, e.g. `BOUNTY_SOLVE__PR=manual`
+const ENV_PREFIX: &str = "BOUNTY_";
+
+/// Separates nested keys in environment variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// A config key split into its parts, e.g. `["repos", "acme/rocket", "transport"]`
+pub type Key = Vec<String>;
+
+/// Where a setting comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Env(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::System(path) => write!(f, "system:{}", path.display()),
+            Self::User(path) => write!(f, "user:{}", path.display()),
+            Self::Env(name) => write!(f, "env:{name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Layer {
+    origin: Origin,
+    table: Table,
+}
+
+/// A setting's effective value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Key,
+    pub value: Value,
+    pub origin: Origin,
+    /// Whether bounty understands the key; unknown keys are kept but ignored
+    pub known: bool,
+}
+
+/// The config as built from the defaults, the system and user config files and `BOUNTY_*`
+/// environment variables, each overriding the ones before
+///
+/// A repository's `.bounty.toml` isn't a layer: only its templates and hooks are used, read
+/// from upstream for bounties in that repository (see [`Config::templates_for`]).
+#[derive(Debug, Clone)]
+pub struct Layers {
+    layers: Vec<Layer>,
+}
+
+impl Layers {
+    pub fn load() -> Result<Self> {
+        let mut layers = vec![Layer {
+            origin: Origin::Default,
+            table: defaults(),
+        }];
+        let files = system_path()
+            .map(Origin::System)
+            .into_iter()
+            .chain([Origin::User(config_path()?)]);
+        for origin in files {
+            layers.extend(read_file(origin)?);
+        }
+        layers.extend(env_layers(std::env::vars())?);
+        Ok(Self { layers })
+    }
+
+    /// All layers merged into a [`Config`]
+    pub fn config(&self) -> Result<Config> {
+        let mut merged = Table::new();
+        for layer in &self.layers {
+            merge(&mut merged, &layer.table);
+        }
+        Value::Table(merged).try_into().wrap_err("invalid config")
+    }
+
+    /// What's in the user config file
+    #[must_use]
+    pub fn user(&self) -> Table {
+        self.layers
+            .iter()
+            .find(|layer| matches!(layer.origin, Origin::User(_)))
+            .map(|layer| layer.table.clone())
+            .unwrap_or_default()
+    }
+
+    /// Every setting with its effective value and where that came from, sorted by key
+    #[must_use]
+    pub fn entries(&self) -> Vec<Entry> {
+        // Later layers override earlier ones when collected
+        let effective: BTreeMap<_, _> = self
+            .layers
+            .iter()
+            .flat_map(|layer| {
+                leaves(&layer.table)
+                    .into_iter()
+                    .map(move |(key, value)| (key, (value, &layer.origin)))
+            })
+            .collect();
+        effective
+            .into_iter()
+            .map(|(key, (value, origin))| Entry {
+                known: *origin == Origin::Default || is_known(&key, &value).unwrap_or(false),
+                key,
+                value,
+                origin: origin.clone(),
+            })
+            .collect()
+    }
+
+    /// The environment variable overriding `key`, if any
+    #[must_use]
+    pub fn env_override(&self, key: &[String]) -> Option<&str> {
+        self.layers
+            .iter()
+            .rev()
+            .find_map(|layer| match &layer.origin {
+                Origin::Env(name) if get(&layer.table, key).is_some() => Some(name.as_str()),
+                _ => None,
+            })
+    }
+
+    /// The user config file's contents after saving `config`
+    ///
+    /// Only settings already in the file or differing from the defaults and system config are
+    /// written, values from environment variables never are, and keys bounty doesn't know
+    /// (e.g. from a newer version) are kept.
+    pub fn user_table_for(&self, config: &Config) -> Result<Table> {
+        let Value::Table(wanted) = Value::try_from(config)? else {
+            bail!("config didn't serialize to a table");
+        };
+        let user = self.user();
+        let mut base = Table::new();
+        let lower = self
+            .layers
+            .iter()
+            .filter(|layer| matches!(layer.origin, Origin::Default | Origin::System(_)));
+        for layer in lower {
+            merge(&mut base, &layer.table);
+        }
+        let env_keys: BTreeSet<Key> = self
+            .layers
+            .iter()
+            .filter(|layer| matches!(layer.origin, Origin::Env(_)))
+            .flat_map(|layer| leaves(&layer.table).into_keys())
+            .collect();
+
+        let saved = leaves(&wanted).into_iter().filter_map(|(key, value)| {
+            let value = saved_value(&key, value, &user, &base, &env_keys)?;
+            Some((key, value))
+        });
+        let unknown = leaves(&user)
+            .into_iter()
+            .filter(|(key, value)| !is_known(key, value).unwrap_or(true));
+        let mut table = Table::new();
+        for (key, value) in saved.chain(unknown) {
+            set(&mut table, &key, value)?;
+        }
+        Ok(table)
+    }
+}
+
+/// What to save in the user config file for a setting whose value is `value`
+fn saved_value(
+    key: &Key,
+    value: Value,
+    user: &Table,
+    base: &Table,
+    env_keys: &BTreeSet<Key>,
+) -> Option<Value> {
+    if env_keys.contains(key) {
+        return get(user, key).cloned();
+    }
+    (get(user, key).is_some() || get(base, key) != Some(&value)).then_some(value)
+}
+
+/// Write the user config file, keeping its comments and formatting
+pub fn write_user(table: &Table) -> Result<()> {
+    let path = config_path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut document: toml_edit::DocumentMut = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| contents.parse().ok())
+        .unwrap_or_default();
+
+    let mut table = table.clone();
+    table
+        .entry("version")
+        .or_insert(Value::Integer(migrate::CURRENT_VERSION.into()));
+    migrate::sync(document.as_table_mut(), &table)?;
+    std::fs::write(&path, document.to_string())
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// Parse a dotted key like `repos."acme/rocket".transport`
+pub fn parse_key(key: &str) -> Result<Key> {
+    let parts = toml_edit::Key::parse(key).wrap_err_with(|| format!("invalid key {key}"))?;
+    Ok(parts.iter().map(|part| part.get().to_string()).collect())
+}
+
+/// Format a key the way it would be written in a dotted TOML key
+#[must_use]
+pub fn display_key(key: &[String]) -> String {
+    key.iter()
+        .map(|part| toml_edit::Key::new(part.as_str()).to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Interpret `raw` as the value of `key`: as TOML (`true`, `42`, `["a", "b"]`) if that's valid
+/// for the key, otherwise as a string
+///
+/// Returns `None` for keys bounty doesn't know.
+pub fn coerce(key: &[String], raw: &str) -> Result<Option<Value>> {
+    let parsed = toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"));
+    if let Some(parsed) = parsed {
+        if let Ok(known) = is_known(key, &parsed) {
+            return Ok(known.then_some(parsed));
+        }
+    }
+    let string = Value::String(raw.to_string());
+    Ok(is_known(key, &string)?.then_some(string))
+}
+
+/// Whether `key` is a setting bounty understands, or an error if `value` isn't valid for it
+///
+/// Serde drops unknown keys, so a key is known if it survives a round trip through
+/// [`Config`].
+fn is_known(key: &[String], value: &Value) -> Result<bool> {
+    let mut table = Table::new();
+    set(&mut table, key, value.clone())?;
+    let config: Config = Value::Table(table)
+        .try_into()
+        .wrap_err_with(|| format!("invalid value for {}", display_key(key)))?;
+    let Value::Table(round_trip) = Value::try_from(&config)? else {
+        return Ok(false);
+    };
+    Ok(get(&round_trip, key).is_some())
+}
+
+fn defaults() -> Table {
+    match Value::try_from(Config::default()) {
+        Ok(Value::Table(table)) => table,
+        _ => Table::new(),
+    }
+}
+
+/// `/etc/bounty/config.toml`, or `%PROGRAMDATA%\bounty\config.toml` on Windows
+fn system_path() -> Option<PathBuf> {
+    if cfg!(windows) {
+        std::env::var_os("PROGRAMDATA").map(|dir| PathBuf::from(dir).join("bounty/config.toml"))
+    } else {
+        Some(PathBuf::from("/etc/bounty/config.toml"))
+    }
+}
+
+/// The user config file's settings, upgraded but not validated, so that a broken setting
+/// can be fixed
+pub fn read_user() -> Result<Table> {
+    let path = config_path()?;
+    let Some(contents) = read_contents(&path)? else {
+        return Ok(Table::new());
+    };
+    let contents = migrate::upgrade_file(&path, contents)?;
+    toml::from_str(&contents).wrap_err_with(|| format!("invalid TOML in {}", path.display()))
+}
+
+fn read_contents(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err).wrap_err_with(|| format!("failed to read {}", path.display())),
+    }
+}
+
+/// Read and validate a config file, if it exists
+fn read_file(origin: Origin) -> Result<Option<Layer>> {
+    let (Origin::System(path) | Origin::User(path)) = &origin else {
+        return Ok(None);
+    };
+    let Some(contents) = read_contents(path)? else {
+        return Ok(None);
+    };
+    // The system config isn't ours to rewrite, so it's only upgraded in memory
+    let contents = match &origin {
+        Origin::User(_) => migrate::upgrade_file(path, contents)?,
+        _ => migrate::upgrade(&contents)?.map_or(contents, |(_, upgraded)| upgraded),
+    };
+    toml::from_str::<Config>(&contents)
+        .wrap_err_with(|| format!("invalid config in {}", path.display()))?;
+    Ok(Some(Layer {
+        table: toml::from_str(&contents)?,
+        origin,
+    }))
+}
+
+/// One layer per `BOUNTY_*` variable naming a setting, e.g. `BOUNTY_GIT__TRANSPORT=ssh`
+///
+/// Other `BOUNTY_*` variables, like the ones set for post-start shells, are ignored.
+fn env_layers(vars: impl Iterator<Item = (String, String)>) -> Result<Vec<Layer>> {
+    let mut vars: Vec<_> = vars
+        .filter_map(|(name, raw)| {
+            let key = env_key(&name)?;
+            Some((name, key, raw))
+        })
+        .collect();
+    vars.sort();
+
+    let mut layers = Vec::new();
+    for (name, key, raw) in vars {
+        let Some(value) = coerce(&key, &raw).wrap_err_with(|| format!("invalid ${name}"))? else {
+            continue;
+        };
+        let mut table = Table::new();
+        set(&mut table, &key, value)?;
+        layers.push(Layer {
+            origin: Origin::Env(name),
+            table,
+        });
+    }
+    Ok(layers)
+}
+
+fn env_key(name: &str) -> Option<Key> {
+    let key = name.strip_prefix(ENV_PREFIX)?;
+    Some(
+        key.to_lowercase()
+            .split(ENV_SEPARATOR)
+            .map(String::from)
+            .collect(),
+    )
+}
+
+/// Deep-merge `from` into `into`, `from` winning
+fn merge(into: &mut Table, from: &Table) {
+    for (name, value) in from {
+        match (into.get_mut(name), value) {
+            (Some(Value::Table(existing)), Value::Table(value)) => merge(existing, value),
+            _ => {
+                into.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Every non-table value in `table`, keyed by its path (arrays count as single values)
+fn leaves(table: &Table) -> BTreeMap<Key, Value> {
+    let mut leaves = BTreeMap::new();
+    collect_leaves(table, &mut Vec::new(), &mut leaves);
+    leaves
+}
+
+fn collect_leaves(table: &Table, prefix: &mut Key, leaves: &mut BTreeMap<Key, Value>) {
+    for (name, value) in table {
+        prefix.push(name.clone());
+        if let Value::Table(nested) = value {
+            collect_leaves(nested, prefix, leaves);
+        } else {
+            leaves.insert(prefix.clone(), value.clone());
+        }
+        prefix.pop();
+    }
+}
+
+#[must_use]
+pub fn get<'a>(table: &'a Table, key: &[String]) -> Option<&'a Value> {
+    let (last, parents) = key.split_last()?;
+    let mut table = table;
+    for part in parents {
+        table = table.get(part)?.as_table()?;
+    }
+    table.get(last)
+}
+
+pub fn set(table: &mut Table, key: &[String], value: Value) -> Result<()> {
+    let Some((last, parents)) = key.split_last() else {
+        bail!("empty config key");
+    };
+    let mut table = table;
+    for part in parents {
+        let entry = table
+            .entry(part.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        let Value::Table(nested) = entry else {
+            bail!("{} is not a table", display_key(key));
+        };
+        table = nested;
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
+
+/// Remove `key`, and any tables left empty, returning its value
+pub fn remove(table: &mut Table, key: &[String]) -> Option<Value> {
+    let (first, rest) = key.split_first()?;
+    if rest.is_empty() {
+        return table.remove(first);
+    }
+    let nested = table.get_mut(first)?.as_table_mut()?;
+    let removed = remove(nested, rest);
+    if nested.is_empty() {
+        table.remove(first);
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> Key {
+        parse_key(key).unwrap()
+    }
+
+    fn layer(origin: Origin, toml: &str) -> Layer {
+        Layer {
+            origin,
+            table: toml::from_str(toml).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display_keys() {
+        let parsed = key(r#"repos."acme/rocket".transport"#);
+        assert_eq!(parsed, ["repos", "acme/rocket", "transport"]);
+        assert_eq!(display_key(&parsed), r#"repos."acme/rocket".transport"#);
+    }
+
+    #[test]
+    fn test_coerce_validates_against_the_schema() {
+        assert_eq!(
+            coerce(&key("solve.sync_fork"), "true").unwrap(),
+            Some(Value::Boolean(true))
+        );
+        // Strings don't need quoting, even when they'd parse as something else
+        assert_eq!(
+            coerce(&key("solve.editor"), "42").unwrap(),
+            Some(Value::String("42".to_string()))
+        );
+        assert_eq!(coerce(&key("solve.editr"), "code").unwrap(), None);
+        assert!(coerce(&key("git.transport"), "carrier-pigeon").is_err());
+    }
+
+    #[test]
+    fn test_env_layers_only_take_settings() {
+        let vars = [
+            ("BOUNTY_SOLVE__PR", "manual"),
+            ("BOUNTY_REPO", "acme/rocket"),
+            ("PATH", "/usr/bin"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let layers = env_layers(vars.into_iter()).unwrap();
+
+        assert_eq!(layers.len(), 1);
+        assert_eq!(
+            layers[0].origin,
+            Origin::Env("BOUNTY_SOLVE__PR".to_string())
+        );
+        assert_eq!(
+            get(&layers[0].table, &key("solve.pr")),
+            Some(&Value::String("manual".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_saving_writes_only_the_user_layer() {
+        let layers = Layers {
+            layers: vec![
+                Layer {
+                    origin: Origin::Default,
+                    table: defaults(),
+                },
+                layer(
+                    Origin::System(PathBuf::from("system.toml")),
+                    "[solve]\nsync_fork = true",
+                ),
+                layer(
+                    Origin::User(PathBuf::from("config.toml")),
+                    "workspace_dir = \"/src\"\nfuture_setting = 1\n[git]\ntransport = \"ssh\"",
+                ),
+                layer(
+                    Origin::Env("BOUNTY_GIT__TRANSPORT".to_string()),
+                    "[git]\ntransport = \"token\"",
+                ),
+            ],
+        };
+        let mut config = layers.config().unwrap();
+        assert!(config.solve.sync_fork);
+        config.solve.editor = Some("zed".to_string());
+
+        let saved = layers.user_table_for(&config).unwrap();
+        let expected: Table = toml::from_str(
+            "workspace_dir = \"/src\"\nfuture_setting = 1\n[git]\ntransport = \"ssh\"\n[solve]\neditor = \"zed\"",
+        )
+        .unwrap();
+        assert_eq!(saved, expected);
+
+        let origin = |name: &str| {
+            layers
+                .entries()
+                .into_iter()
+                .find(|entry| entry.key == key(name))
+                .unwrap()
+        };
+        assert_eq!(
+            origin("git.transport").origin,
+            Origin::Env("BOUNTY_GIT__TRANSPORT".to_string())
+        );
+        assert_eq!(origin("solve.commit").origin, Origin::Default);
+        assert!(!origin("future_setting").known);
+    }
+}
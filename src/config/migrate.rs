@@ -0,0 +1,249 @@
+use eyre::{Result, WrapErr};
+use owo_colors::OwoColorize;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Version of the config file format written by this build
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Upgrades a config document by one version
+type Migration = fn(&mut DocumentMut);
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`
+const MIGRATIONS: &[Migration] = &[stamp_version];
+
+/// The version a config file was written for; files from before versioning are version 0
+pub fn version_of(document: &DocumentMut) -> Result<u32> {
+    let Some(version) = document.get("version") else {
+        return Ok(0);
+    };
+    version
+        .as_integer()
+        .and_then(|version| u32::try_from(version).ok())
+        .ok_or_else(|| eyre::eyre!("invalid config version {version}"))
+}
+
+/// Upgrade `contents` to [`CURRENT_VERSION`], keeping comments and keys this build doesn't
+/// know, or `None` if it's already current (or from a newer bounty)
+pub fn upgrade(contents: &str) -> Result<Option<(u32, String)>> {
+    let mut document: DocumentMut = contents.parse()?;
+    let version = version_of(&document)?;
+    if version >= CURRENT_VERSION {
+        return Ok(None);
+    }
+
+    for migration in &MIGRATIONS[usize::try_from(version)?..] {
+        migration(&mut document);
+    }
+    document.insert("version", toml_edit::value(i64::from(CURRENT_VERSION)));
+    Ok(Some((version, document.to_string())))
+}
+
+/// Upgrade the config file at `path`, keeping the original next to it, and return its new
+/// contents
+pub fn upgrade_file(path: &Path, contents: String) -> Result<String> {
+    let Some((version, upgraded)) =
+        upgrade(&contents).wrap_err_with(|| format!("invalid config in {}", path.display()))?
+    else {
+        return Ok(contents);
+    };
+
+    let backup = path.with_extension(format!("toml.v{version}.bak"));
+    if !backup.exists() {
+        std::fs::write(&backup, &contents)
+            .wrap_err_with(|| format!("failed to back up {}", path.display()))?;
+    }
+    std::fs::write(path, &upgraded)
+        .wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    eprintln!(
+        "{} Upgraded {} to version {CURRENT_VERSION} (the original is in {})",
+        "ℹ".bright_blue(),
+        path.display(),
+        backup.display()
+    );
+    Ok(upgraded)
+}
+
+/// 0 → 1: files from before versioning only hold `github_token` and
+/// `has_completed_first_time_setup`, which keep their meaning, so they just get a `version`
+fn stamp_version(_document: &mut DocumentMut) {}
+
+/// Bring `document` in line with `table`, only touching what changed so comments and
+/// formatting survive
+pub fn sync(document: &mut dyn TableLike, table: &toml::Table) -> Result<()> {
+    let stale: Vec<String> = document
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !table.contains_key(key))
+        .collect();
+    for key in stale {
+        document.remove(&key);
+    }
+    for (key, value) in table {
+        sync_item(document, key, value)?;
+    }
+    Ok(())
+}
+
+fn sync_item(document: &mut dyn TableLike, key: &str, value: &toml::Value) -> Result<()> {
+    if let toml::Value::Table(nested) = value {
+        if let Some(existing) = document.get_mut(key).and_then(Item::as_table_like_mut) {
+            return sync(existing, nested);
+        }
+        let mut new = toml_edit::Table::new();
+        new.set_implicit(true);
+        sync(&mut new, nested)?;
+        document.insert(key, Item::Table(new));
+        return Ok(());
+    }
+
+    if let (toml::Value::Array(items), Some(Item::ArrayOfTables(existing))) =
+        (value, document.get_mut(key))
+    {
+        if let Some(tables) = items
+            .iter()
+            .map(toml::Value::as_table)
+            .collect::<Option<Vec<_>>>()
+        {
+            return sync_array_of_tables(existing, &tables);
+        }
+    }
+
+    let mut new: toml_edit::Value = value
+        .to_string()
+        .parse()
+        .wrap_err_with(|| format!("failed to write {key}"))?;
+    let Some(Item::Value(existing)) = document.get_mut(key) else {
+        document.insert(key, Item::Value(new));
+        return Ok(());
+    };
+    if !same(existing, value) {
+        *new.decor_mut() = existing.decor().clone();
+        *existing = new;
+    }
+    Ok(())
+}
+
+/// Sync `[[key]]` sections one by one, so unchanged ones keep their comments and stay
+/// sections rather than becoming an inline array
+fn sync_array_of_tables(
+    existing: &mut toml_edit::ArrayOfTables,
+    tables: &[&toml::Table],
+) -> Result<()> {
+    while existing.len() > tables.len() {
+        existing.remove(existing.len() - 1);
+    }
+    for (section, table) in existing.iter_mut().zip(tables) {
+        sync(section, table)?;
+    }
+    for table in tables.iter().skip(existing.len()) {
+        let mut section = toml_edit::Table::new();
+        sync(&mut section, table)?;
+        existing.push(section);
+    }
+    Ok(())
+}
+
+fn same(existing: &toml_edit::Value, value: &toml::Value) -> bool {
+    let mut existing = existing.clone();
+    existing.decor_mut().clear();
+    toml::from_str::<toml::Table>(&format!("value = {existing}"))
+        .is_ok_and(|table| table.get("value") == Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_reach_current_version() {
+        assert_eq!(MIGRATIONS.len(), CURRENT_VERSION as usize);
+    }
+
+    #[test]
+    fn test_v0_gets_a_version() {
+        let (version, upgraded) = upgrade(
+            r#"github_token = "ghp_token" # from bounty login
+has_completed_first_time_setup = true
+"#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(version, 0);
+        let upgraded: DocumentMut = upgraded.parse().unwrap();
+        assert_eq!(version_of(&upgraded).unwrap(), CURRENT_VERSION);
+        assert!(upgraded
+            .to_string()
+            .starts_with("github_token = \"ghp_token\" # from bounty login\n"));
+        let config: crate::Config = toml::from_str(&upgraded.to_string()).unwrap();
+        assert_eq!(config.github_token.as_deref(), Some("ghp_token"));
+        assert!(config.has_completed_first_time_setup);
+    }
+
+    #[test]
+    fn test_upgrade_leaves_current_and_newer_files() {
+        assert!(upgrade("version = 1").unwrap().is_none());
+        assert!(upgrade("version = 99\nfuture = true").unwrap().is_none());
+        assert!(upgrade("version = \"one\"").is_err());
+    }
+
+    #[test]
+    fn test_sync_keeps_comments_and_unchanged_values() {
+        let mut document: DocumentMut = r#"# bounty settings
+workspace_dir = "/src" # fast disk
+
+[solve]
+pr = "manual" # I'll open it myself
+commit = true
+
+# repos I care about
+[[watch.filters]]
+owner = "acme" # mine
+
+[[watch.filters]]
+keywords = ["rust"]
+"#
+        .parse()
+        .unwrap();
+        let table: toml::Table = toml::from_str(
+            r#"
+workspace_dir = "/src"
+
+[solve]
+pr = "now"
+
+[repos."acme/rocket"]
+transport = "ssh"
+
+[[watch.filters]]
+owner = "acme"
+
+[[watch.filters]]
+keywords = ["rust", "cli"]
+"#,
+        )
+        .unwrap();
+
+        sync(document.as_table_mut(), &table).unwrap();
+        assert_eq!(
+            document.to_string(),
+            r#"# bounty settings
+workspace_dir = "/src" # fast disk
+
+[solve]
+pr = "now" # I'll open it myself
+
+# repos I care about
+[[watch.filters]]
+owner = "acme" # mine
+
+[[watch.filters]]
+keywords = ["rust", "cli"]
+
+[repos."acme/rocket"]
+transport = "ssh"
+"#
+        );
+    }
+}
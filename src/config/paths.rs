@@ -0,0 +1,119 @@
+use eyre::{eyre, Result, WrapErr};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming the config file, also set by `--config`
+pub const CONFIG_ENV: &str = "BOUNTY_CONFIG";
+
+/// Directory holding the config file: `$XDG_CONFIG_HOME/bounty`, or `~/.config/bounty`
+pub fn config_dir() -> Result<PathBuf> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", &home()?, ".config").join("bounty"))
+}
+
+/// Path of the user's config file: `$BOUNTY_CONFIG`, or `config.toml` in [`config_dir`]
+pub fn config_path() -> Result<PathBuf> {
+    match std::env::var_os(CONFIG_ENV) {
+        Some(path) if !path.is_empty() => Ok(PathBuf::from(path)),
+        _ => Ok(config_dir()?.join("config.toml")),
+    }
+}
+
+/// Directory for data that can be fetched again: `$XDG_CACHE_HOME/bounty`, or
+/// `~/.cache/bounty`
+pub fn cache_dir() -> Result<PathBuf> {
+    Ok(xdg_dir("XDG_CACHE_HOME", &home()?, ".cache").join("bounty"))
+}
+
+/// Directory for what bounty remembers between runs, like the bounties you're tracking:
+/// `$XDG_STATE_HOME/bounty`, or `~/.local/state/bounty`
+pub fn state_dir() -> Result<PathBuf> {
+    Ok(xdg_dir("XDG_STATE_HOME", &home()?, ".local/state").join("bounty"))
+}
+
+/// Path of cache file `name`
+pub fn cache_file(name: &str) -> Result<PathBuf> {
+    moved_from_config_dir(&cache_dir()?, &home()?, name)
+}
+
+/// Path of state file `name`
+pub fn state_file(name: &str) -> Result<PathBuf> {
+    moved_from_config_dir(&state_dir()?, &home()?, name)
+}
+
+/// `$var` if it's set to an absolute path, as the XDG spec requires, otherwise `home/default`
+#[must_use]
+pub fn xdg_dir(var: &str, home: &Path, default: &str) -> PathBuf {
+    xdg_dir_from(std::env::var_os(var), home, default)
+}
+
+/// [`xdg_dir`] with the variable's value already read
+fn xdg_dir_from(value: Option<OsString>, home: &Path, default: &str) -> PathBuf {
+    value
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home.join(default))
+}
+
+fn home() -> Result<PathBuf> {
+    dirs::home_dir().ok_or_else(|| eyre!("could not determine home directory"))
+}
+
+/// `dir/name`, first moving the file out of `home/.config/bounty`, where older versions
+/// kept everything, if it isn't in `dir` yet
+fn moved_from_config_dir(dir: &Path, home: &Path, name: &str) -> Result<PathBuf> {
+    let path = dir.join(name);
+    if path.exists() {
+        return Ok(path);
+    }
+    let legacy = home.join(".config/bounty").join(name);
+    if !legacy.is_file() {
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+    // Renaming fails across filesystems, so fall back to copying
+    if std::fs::rename(&legacy, &path).is_err() {
+        std::fs::copy(&legacy, &path).wrap_err_with(|| {
+            format!("failed to move {} to {}", legacy.display(), path.display())
+        })?;
+        let _ = std::fs::remove_file(&legacy);
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xdg_dir_ignores_relative_paths() {
+        let home = Path::new("/home/me");
+
+        assert_eq!(
+            xdg_dir_from(Some("relative/dir".into()), home, ".cache"),
+            Path::new("/home/me/.cache")
+        );
+        assert_eq!(
+            xdg_dir_from(Some("/xdg".into()), home, ".cache"),
+            Path::new("/xdg")
+        );
+        assert_eq!(
+            xdg_dir_from(None, home, ".local/state"),
+            Path::new("/home/me/.local/state")
+        );
+    }
+
+    #[test]
+    fn test_moves_legacy_file_into_xdg_state_home() {
+        let home = tempfile::tempdir().unwrap();
+        let legacy = home.path().join(".config/bounty/test_legacy.json");
+        std::fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+        std::fs::write(&legacy, "[]").unwrap();
+        let state_dir = home.path().join("state/bounty");
+
+        let path = moved_from_config_dir(&state_dir, home.path(), "test_legacy.json").unwrap();
+        assert_eq!(path, state_dir.join("test_legacy.json"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[]");
+        assert!(!legacy.exists());
+    }
+}
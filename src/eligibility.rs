@@ -0,0 +1,111 @@
+use crate::github::LinkedPullRequest;
+use octocrab::models::issues::Issue;
+use octocrab::models::IssueState;
+use owo_colors::OwoColorize;
+
+/// A reason working on an issue is likely to be wasted effort
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    Closed,
+    Locked,
+    AssignedTo(Vec<String>),
+    CompetingPullRequests(Vec<LinkedPullRequest>),
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => write!(f, "the issue is closed"),
+            Self::Locked => write!(f, "the issue is locked"),
+            Self::AssignedTo(assignees) => {
+                write!(f, "the issue is assigned to {}", assignees.join(", "))
+            }
+            Self::CompetingPullRequests(prs) => {
+                write!(f, "other open pull requests reference the issue:")?;
+                prs.iter()
+                    .try_for_each(|pr| write!(f, "\n    {} {}", pr.author.bold(), pr.url.dimmed()))
+            }
+        }
+    }
+}
+
+/// Find everything that makes an issue a poor choice for `user`
+#[must_use]
+pub fn check(issue: &Issue, linked_prs: &[LinkedPullRequest], user: &str) -> Vec<Problem> {
+    let assignees: Vec<String> = issue
+        .assignees
+        .iter()
+        .map(|assignee| assignee.login.clone())
+        .filter(|login| !login.eq_ignore_ascii_case(user))
+        .collect();
+
+    check_status(issue.state == IssueState::Closed, issue.locked, assignees)
+        .into_iter()
+        .chain(check_competing(linked_prs, user))
+        .collect()
+}
+
+fn check_status(closed: bool, locked: bool, assignees: Vec<String>) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if closed {
+        problems.push(Problem::Closed);
+    }
+    if locked {
+        problems.push(Problem::Locked);
+    }
+    if !assignees.is_empty() {
+        problems.push(Problem::AssignedTo(assignees));
+    }
+    problems
+}
+
+fn check_competing(linked_prs: &[LinkedPullRequest], user: &str) -> Option<Problem> {
+    let competing: Vec<LinkedPullRequest> = linked_prs
+        .iter()
+        .filter(|pr| pr.open && !pr.author.eq_ignore_ascii_case(user))
+        .cloned()
+        .collect();
+
+    (!competing.is_empty()).then_some(Problem::CompetingPullRequests(competing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(author: &str, open: bool) -> LinkedPullRequest {
+        LinkedPullRequest {
+            author: author.to_string(),
+            url: format!("https://github.com/o/r/pull/{author}"),
+            open,
+        }
+    }
+
+    #[test]
+    fn test_open_unassigned_issue_has_no_problems() {
+        assert!(check_status(false, false, Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_status_problems() {
+        let problems = check_status(true, true, vec!["someone".to_string()]);
+        assert_eq!(
+            problems,
+            [
+                Problem::Closed,
+                Problem::Locked,
+                Problem::AssignedTo(vec!["someone".to_string()])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_competing_ignores_own_and_closed_prs() {
+        let prs = [pr("me", true), pr("rival", false), pr("other", true)];
+        assert_eq!(
+            check_competing(&prs, "Me"),
+            Some(Problem::CompetingPullRequests(vec![pr("other", true)]))
+        );
+        assert_eq!(check_competing(&prs[..2], "me"), None);
+    }
+}
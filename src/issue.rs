@@ -1,24 +1,28 @@
-use crate::bountybot::BountyBotClient;
+use crate::sources::{fetch_all, QuestSource};
 use eyre::Result;
 use inquire::{Select, Text};
 
-/// Prompts the user to select or enter an issue reference
-pub async fn prompt_issue_reference() -> Result<String> {
-    let client = BountyBotClient::new();
-    let quests = client.fetch_active_quests().await?;
+/// Prompts the user to select a quest from the given sources or enter an issue reference
+pub async fn prompt_issue_reference(sources: &[Box<dyn QuestSource>]) -> Result<String> {
+    let fetched = fetch_all(sources).await;
+    if !fetched.all_failed() {
+        crate::quest_cache::store(&fetched.quests);
+    }
+    let quests = fetched.quests;
 
     if quests.is_empty() {
         return Ok(Text::new("Enter the issue reference (e.g., owner/repo#123):").prompt()?);
     }
 
-    println!("🔍 Select an active quest or enter your own:");
+    // On stderr, like the prompts, so `bounty solve --shell` prints only the `cd`
+    eprintln!("🔍 Select an active quest or enter your own:");
 
     let selection = Select::new("Choose a quest:", quests)
         .with_help_message("↑↓ to move, enter to select, type to filter")
         .with_vim_mode(true);
 
     match selection.prompt() {
-        Ok(selected) => Ok(selected.repo_ref),
+        Ok(selected) => Ok(selected.repo_ref()),
         Err(inquire::InquireError::OperationCanceled) => {
             Text::new("Enter the issue reference (e.g., owner/repo#123):")
                 .prompt()
@@ -10,16 +10,33 @@ use clap::Parser;
 
 mod animation;
 mod bountybot;
+mod branch;
 pub mod command;
 pub mod config;
+mod eligibility;
+mod git;
 mod github;
+mod hooks;
 mod issue;
+pub mod logging;
+mod notify;
 mod parse;
+mod post_start;
+mod pr_template;
+mod quest_cache;
+pub mod sources;
+mod state;
+pub mod telemetry;
+mod template;
+#[cfg(test)]
+mod test_env;
+mod watch;
 
 pub use animation::show_welcome_animation;
 pub use bountybot::{BountyBotClient, QuestIssue};
 pub use command::{handle, handle_default_command, Command};
 pub use config::Config;
+pub use git::allow_worktree_config;
 pub use github::{login, GitHub, CLIENT_ID};
 pub use issue::prompt_issue_reference;
 pub use parse::RepoIssue;
@@ -37,6 +54,18 @@ pub struct Cli {
     /// The bounty command to execute
     #[command(subcommand)]
     pub command: Option<Command>,
+
+    /// Use this config file instead of ~/.config/bounty/config.toml
+    #[arg(long, global = true, value_name = "PATH", env = config::CONFIG_ENV)]
+    pub config: Option<std::path::PathBuf>,
+
+    /// Log more: -v for debug logs, -vv for everything
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
 }
 
 fn get_styles() -> clap::builder::Styles {
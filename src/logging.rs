@@ -0,0 +1,162 @@
+use eyre::Result;
+use indicatif::MultiProgress;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing_error::ErrorLayer;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// How many runs' log files are kept
+const LOGS_KEPT: usize = 20;
+
+/// What the log file records, whatever the verbosity
+const FILE_DIRECTIVES: &str = "info,bounty=debug";
+
+/// Shared by every progress bar, so log lines can be printed above them
+static PROGRESS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Where progress bars go; logs printed meanwhile don't tear them
+#[must_use]
+pub fn progress() -> MultiProgress {
+    PROGRESS.clone()
+}
+
+/// Log to stderr at the level chosen by `-v`/`-q` (or `RUST_LOG`), and, if `to_file`, as
+/// JSON to a new file in [`log_dir`]
+pub fn install(verbose: u8, quiet: bool, to_file: bool) {
+    let stderr_filter = stderr_directives(verbose, quiet).map_or_else(
+        || EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        EnvFilter::new,
+    );
+    let stderr_layer = fmt::layer()
+        .with_target(false)
+        .pretty()
+        .with_ansi(true)
+        .with_writer(ProgressWriter)
+        .with_filter(stderr_filter);
+
+    let file = to_file.then(open_log_file).and_then(Result::ok);
+    let file_layer = file.map(|file| {
+        fmt::layer()
+            .json()
+            .with_writer(Mutex::new(file))
+            .with_filter(EnvFilter::new(FILE_DIRECTIVES))
+    });
+
+    tracing_subscriber::registry()
+        .with(stderr_layer)
+        .with(file_layer)
+        .with(ErrorLayer::default())
+        .init();
+}
+
+/// `None` leaves it to `RUST_LOG`
+const fn stderr_directives(verbose: u8, quiet: bool) -> Option<&'static str> {
+    match (verbose, quiet) {
+        (_, true) => Some("error"),
+        (0, false) => None,
+        (1, false) => Some("info,bounty=debug"),
+        _ => Some("debug,bounty=trace"),
+    }
+}
+
+/// Where each run's log file goes
+pub fn log_dir() -> Result<PathBuf> {
+    Ok(crate::config::state_dir()?.join("logs"))
+}
+
+/// Log files, newest first
+pub fn log_files() -> Result<Vec<PathBuf>> {
+    let dir = log_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "log"))
+        .collect();
+    // Named after the time they were started, so this sorts newest first
+    files.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(files)
+}
+
+fn open_log_file() -> Result<File> {
+    let dir = log_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis());
+    let file = File::create(dir.join(format!("{started:015}-{}.log", std::process::id())))?;
+    prune(&log_files()?, LOGS_KEPT);
+    Ok(file)
+}
+
+/// Delete all but the first `kept` of `files`
+fn prune(files: &[PathBuf], kept: usize) {
+    for file in files.iter().skip(kept) {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// Writes stderr logs with the progress bars hidden, one event at a time
+struct ProgressWriter;
+
+impl<'a> MakeWriter<'a> for ProgressWriter {
+    type Writer = SuspendedStderr;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SuspendedStderr(Vec::new())
+    }
+}
+
+/// An event's output, printed once it's complete
+struct SuspendedStderr(Vec<u8>);
+
+impl Write for SuspendedStderr {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for SuspendedStderr {
+    fn drop(&mut self) {
+        PROGRESS.suspend(|| std::io::stderr().write_all(&self.0).ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_directives() {
+        assert_eq!(stderr_directives(0, false), None);
+        assert_eq!(stderr_directives(1, false), Some("info,bounty=debug"));
+        assert_eq!(stderr_directives(3, false), Some("debug,bounty=trace"));
+        assert_eq!(stderr_directives(0, true), Some("error"));
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: Vec<_> = ["3-1.log", "2-1.log", "1-1.log"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for file in &files {
+            std::fs::write(file, "{}").unwrap();
+        }
+
+        prune(&files, 2);
+        assert!(files[0].exists() && files[1].exists());
+        assert!(!files[2].exists());
+    }
+}
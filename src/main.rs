@@ -6,55 +6,43 @@
     clippy::missing_const_for_fn
 )]
 
-use bounty::{command, config, Cli};
+use bounty::telemetry::{self, Scrubber};
+use bounty::{command, config, logging, Cli};
 use clap::Parser;
 
-// Build-time constant for Sentry DSN
-const SENTRY_DSN: &str = match option_env!("SENTRY_DSN") {
-    Some(dsn) => dsn,
-    None => "",
-};
-
-fn install_tracing() {
-    use tracing_error::ErrorLayer;
-    use tracing_subscriber::prelude::*;
-    use tracing_subscriber::{fmt, EnvFilter};
-
-    let fmt_layer = fmt::layer().with_target(false).pretty().with_ansi(true);
-    let filter_layer = EnvFilter::try_from_default_env()
-        .or_else(|_| EnvFilter::try_new("info"))
-        .unwrap();
-
-    tracing_subscriber::registry()
-        .with(filter_layer)
-        .with(fmt_layer)
-        .with(ErrorLayer::default())
-        .init();
-}
-
 fn main() -> eyre::Result<()> {
+    let cli = Cli::parse();
+
     // Set up tracing subscriber with error layer
-    install_tracing();
+    let keeps_log = cli.command.as_ref().is_none_or(command::Command::keeps_log);
+    logging::install(cli.verbose, cli.quiet, keeps_log);
 
     // Install color-eyre with spantrace support
     color_eyre::install()?;
 
-    // Check if Sentry is disabled at runtime
-    let sentry_disabled = std::env::var("DISABLE_SENTRY").is_ok();
+    // Everything finds the config file through the environment, including the bounty
+    // processes we spawn. Set before any other threads exist.
+    if let Some(path) = &cli.config {
+        std::env::set_var(config::CONFIG_ENV, std::path::absolute(path)?);
+    }
+
+    // Before any thread could open a repository
+    bounty::allow_worktree_config();
 
-    // Initialize Sentry with build-time DSN only if not disabled
+    // Crash reports need the user's consent, and can be turned off for a single run
+    let config = config::Config::load().ok();
+    let telemetry_enabled = config
+        .as_ref()
+        .is_some_and(|config| config.telemetry.is_enabled());
+    let sentry_disabled = std::env::var("DISABLE_SENTRY").is_ok() || !telemetry_enabled;
+
+    // Initialize Sentry with build-time DSN only if enabled
     let _guard = if sentry_disabled {
         sentry::init(sentry::ClientOptions::default())
     } else {
         sentry::init((
-            SENTRY_DSN,
-            sentry::ClientOptions {
-                release: sentry::release_name!(),
-                default_integrations: true,
-                auto_session_tracking: true,
-                attach_stacktrace: true,
-                ..Default::default()
-            },
+            telemetry::SENTRY_DSN,
+            telemetry::client_options(Scrubber::load(config.as_ref())),
         ))
     };
 
@@ -63,15 +51,12 @@ fn main() -> eyre::Result<()> {
         .enable_all()
         .build()
         .unwrap()
-        .block_on(run())
+        .block_on(run(cli))
 }
 
-async fn run() -> eyre::Result<()> {
-    let cli = Cli::parse();
-    let config = config::Config::load()?;
-
+async fn run(cli: Cli) -> eyre::Result<()> {
     match cli.command {
-        Some(cmd) => command::handle(cmd, config.try_get_github_token().as_deref()).await?,
+        Some(cmd) => command::handle(cmd).await?,
         None => command::handle_default_command().await?,
     }
 
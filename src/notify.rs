@@ -0,0 +1,117 @@
+use crate::QuestIssue;
+use eyre::{bail, Result, WrapErr};
+use owo_colors::OwoColorize;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::time::Duration;
+
+/// How long a webhook gets to respond, so a hung endpoint can't stall `bounty watch`
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A way of telling the user that a matching quest appeared
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notifier {
+    /// Ring the terminal bell and print the quest
+    Bell,
+    /// Show a desktop notification (`notify-send` on Linux, `osascript` on macOS)
+    Desktop,
+    /// POST the quest as JSON to a URL
+    Webhook { url: String },
+    /// Run a command with the platform's shell (`sh -c`, or `cmd /C` on Windows), with the
+    /// quest exposed as `BOUNTY_QUEST_*` environment variables
+    Command { command: String },
+}
+
+impl Notifier {
+    /// Deliver a notification about a single quest
+    pub async fn notify(&self, quest: &QuestIssue) -> Result<()> {
+        match self {
+            Self::Bell => {
+                println!("\x07🔔 {quest}");
+                Ok(())
+            }
+            Self::Desktop => desktop(quest),
+            Self::Webhook { url } => webhook(url, quest).await,
+            Self::Command { command } => run_command(command, quest),
+        }
+    }
+}
+
+fn desktop(quest: &QuestIssue) -> Result<()> {
+    let summary = format!("New bounty: {}", quest.repo_ref());
+
+    let status = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            quest.title, summary
+        );
+        Command::new("osascript").arg("-e").arg(script).status()
+    } else {
+        Command::new("notify-send")
+            .arg("--app-name=bounty")
+            .arg(&summary)
+            .arg(&quest.title)
+            .status()
+    }
+    .wrap_err("failed to launch desktop notifier")?;
+
+    if !status.success() {
+        bail!("desktop notifier exited with {status}");
+    }
+    Ok(())
+}
+
+async fn webhook(url: &str, quest: &QuestIssue) -> Result<()> {
+    reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()?
+        .post(url)
+        .json(&serde_json::json!({
+            "title": quest.title,
+            "issue": quest.repo_ref(),
+            "url": quest.issue.html_url(),
+        }))
+        .send()
+        .await?
+        .error_for_status()
+        .wrap_err("webhook rejected notification")?;
+    Ok(())
+}
+
+fn run_command(command: &str, quest: &QuestIssue) -> Result<()> {
+    let status = shell(command)
+        .env("BOUNTY_QUEST_TITLE", &quest.title)
+        .env("BOUNTY_QUEST_REF", quest.repo_ref())
+        .env("BOUNTY_QUEST_URL", quest.issue.html_url())
+        .status()
+        .wrap_err("failed to run notification command")?;
+
+    if !status.success() {
+        bail!("notification command exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// Send a quest through every notifier, reporting failures without stopping
+pub async fn notify_all(notifiers: &[Notifier], quest: &QuestIssue) {
+    for notifier in notifiers {
+        if let Err(e) = notifier.notify(quest).await {
+            eprintln!("{} {e:#}", "notification failed:".yellow());
+        }
+    }
+}
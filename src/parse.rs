@@ -2,7 +2,7 @@ use eyre::Result;
 use regex::Regex;
 use url::Url;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RepoIssue {
     pub owner: String,
     pub repo: String,
@@ -28,6 +28,17 @@ impl RepoIssue {
         format!("{owner}/{repo}")
     }
 
+    /// The issue's page on github.com
+    #[must_use]
+    pub fn html_url(&self) -> String {
+        let Self {
+            owner,
+            repo,
+            issue_number,
+        } = self;
+        format!("https://github.com/{owner}/{repo}/issues/{issue_number}")
+    }
+
     /// Parse a repository issue reference from various formats:
     /// - Full URL: <https://github.com/owner/repo/issues/123>
     /// - Domain URL: github.com/owner/repo/issues/123
@@ -77,6 +88,12 @@ impl RepoIssue {
     }
 }
 
+impl std::fmt::Display for RepoIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}#{}", self.owner, self.repo, self.issue_number)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +168,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_display_round_trip() {
+        let issue = RepoIssue::parse("ghbountybot/cli/issues/2").unwrap();
+        assert_eq!(issue.to_string(), "ghbountybot/cli#2");
+        assert_eq!(RepoIssue::parse(&issue.to_string()).unwrap(), issue);
+    }
+
     #[test]
     fn test_parse_invalid_input() {
         let inputs = [
@@ -0,0 +1,119 @@
+use crate::config::PostStartAction;
+use crate::state::TrackedBounty;
+use eyre::{bail, Result, WrapErr};
+use std::path::Path;
+use std::process::Command;
+
+/// Carry out a post-start action for a freshly set up bounty
+pub fn run(action: PostStartAction, bounty: &TrackedBounty, editor: Option<&str>) -> Result<()> {
+    let Some(checkout) = &bounty.checkout else {
+        bail!("the bounty has no checkout");
+    };
+
+    match action {
+        PostStartAction::Browser => open_in_browser(bounty),
+        PostStartAction::Editor => {
+            let editor = self::editor(editor)
+                .ok_or_else(|| eyre::eyre!("set solve.editor or $EDITOR to open an editor"))?;
+            open_in_editor(&editor, checkout, bounty)
+        }
+        PostStartAction::Shell => spawn_shell(checkout, bounty),
+    }
+}
+
+/// The configured editor command, falling back to `$VISUAL` and `$EDITOR`
+#[must_use]
+pub fn editor(configured: Option<&str>) -> Option<String> {
+    configured
+        .map(str::to_string)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+}
+
+fn open_in_browser(bounty: &TrackedBounty) -> Result<()> {
+    let urls = std::iter::once(bounty.repo_issue().html_url()).chain(bounty.pr_url.clone());
+    for url in urls {
+        open::that(&url).wrap_err_with(|| format!("failed to open {url}"))?;
+    }
+    Ok(())
+}
+
+fn open_in_editor(editor: &str, checkout: &Path, bounty: &TrackedBounty) -> Result<()> {
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("the editor command is empty");
+    };
+
+    // Terminal editors need the terminal until they exit; GUI editors return right away
+    let status = Command::new(program)
+        .args(words)
+        .arg(checkout)
+        .current_dir(checkout)
+        .envs(bounty_env(bounty))
+        .status()
+        .wrap_err_with(|| format!("failed to run {program}"))?;
+    if !status.success() {
+        bail!("{program} exited with {status}");
+    }
+    Ok(())
+}
+
+/// Start the user's shell in the checkout and wait for them to leave it
+fn spawn_shell(checkout: &Path, bounty: &TrackedBounty) -> Result<()> {
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| default_shell().to_string());
+    eprintln!(
+        "🐚 Starting {shell} in {} (exit to return)",
+        checkout.display()
+    );
+    Command::new(&shell)
+        .current_dir(checkout)
+        .envs(bounty_env(bounty))
+        .status()
+        .wrap_err_with(|| format!("failed to start {shell}"))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+const fn default_shell() -> &'static str {
+    "sh"
+}
+
+#[cfg(not(unix))]
+const fn default_shell() -> &'static str {
+    "cmd"
+}
+
+/// `BOUNTY_*` variables describing the bounty, for the shell and editor
+fn bounty_env(bounty: &TrackedBounty) -> Vec<(&'static str, String)> {
+    let issue = bounty.repo_issue();
+    let mut env = vec![
+        ("BOUNTY_REPO", format!("{}/{}", bounty.owner, bounty.repo)),
+        ("BOUNTY_ISSUE", bounty.issue_number.to_string()),
+        ("BOUNTY_ISSUE_URL", issue.html_url()),
+        ("BOUNTY_BRANCH", bounty.branch.clone()),
+    ];
+    if let Some(pr_url) = &bounty.pr_url {
+        env.push(("BOUNTY_PR_URL", pr_url.clone()));
+    }
+    env
+}
+
+/// A `cd` into `path` for the shell to `eval`
+#[must_use]
+pub fn cd_command(path: &Path) -> String {
+    let quoted = path.display().to_string().replace('\'', r"'\''");
+    format!("cd '{quoted}'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cd_command_quotes_path() {
+        assert_eq!(
+            cd_command(Path::new("/home/me/bounties/it's/repo-1")),
+            r"cd '/home/me/bounties/it'\''s/repo-1'"
+        );
+    }
+}
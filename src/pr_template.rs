@@ -0,0 +1,146 @@
+use crate::github::RepoEntry;
+use crate::GitHub;
+use eyre::Result;
+use inquire::Select;
+
+/// Directories GitHub looks in for pull request templates and contribution guidelines, in
+/// order of preference, with `""` being the repository root
+const DOC_DIRS: &[&str] = &[".github", "", "docs"];
+
+/// A pull request template from the upstream repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrTemplate {
+    /// Path of the template within the repository
+    pub path: String,
+    pub body: String,
+}
+
+impl std::fmt::Display for PrTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.path)
+    }
+}
+
+/// Fetch every pull request template the upstream repository defines
+pub async fn fetch(github: &GitHub, owner: &str, repo: &str) -> Result<Vec<PrTemplate>> {
+    let mut paths = Vec::new();
+    for entry in doc_entries(github, owner, repo).await? {
+        if entry.is_dir && entry.name.eq_ignore_ascii_case("PULL_REQUEST_TEMPLATE") {
+            let files = github.list_directory(owner, repo, &entry.path).await?;
+            paths.extend(
+                files
+                    .into_iter()
+                    .filter(|file| !file.is_dir && is_markdown(&file.name))
+                    .map(|file| file.path),
+            );
+        } else if !entry.is_dir && is_template_file(&entry.name) {
+            paths.push(entry.path);
+        }
+    }
+
+    let mut templates = Vec::new();
+    for path in paths {
+        if let Some(body) = github.get_file(owner, repo, &path).await? {
+            templates.push(PrTemplate { path, body });
+        }
+    }
+    Ok(templates)
+}
+
+/// Link to the repository's contribution guidelines, if it has any
+pub async fn contributing_guide(
+    github: &GitHub,
+    owner: &str,
+    repo: &str,
+) -> Result<Option<String>> {
+    Ok(doc_entries(github, owner, repo)
+        .await?
+        .into_iter()
+        .find(|entry| !entry.is_dir && is_contributing(&entry.name))
+        .map(|entry| format!("https://github.com/{owner}/{repo}/blob/HEAD/{}", entry.path)))
+}
+
+/// Everything in [`DOC_DIRS`], listing each directory once
+async fn doc_entries(github: &GitHub, owner: &str, repo: &str) -> Result<Vec<RepoEntry>> {
+    let mut entries = Vec::new();
+    for dir in DOC_DIRS {
+        entries.extend(github.list_directory(owner, repo, dir).await?);
+    }
+    Ok(entries)
+}
+
+fn is_markdown(name: &str) -> bool {
+    name.to_ascii_lowercase().ends_with(".md")
+}
+
+/// GitHub matches these names in any case
+fn is_template_file(name: &str) -> bool {
+    name.eq_ignore_ascii_case("pull_request_template.md")
+}
+
+/// `CONTRIBUTING`, with or without an extension like `.md`, in any case
+fn is_contributing(name: &str) -> bool {
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    stem.eq_ignore_ascii_case("contributing")
+}
+
+/// Let the user pick a template when the repository has several
+///
+/// Returns `None` if there are no templates or the user chooses not to use one.
+pub fn choose(mut templates: Vec<PrTemplate>) -> Result<Option<PrTemplate>> {
+    if templates.len() <= 1 {
+        return Ok(templates.pop());
+    }
+
+    match Select::new(
+        "This repository has several PR templates, pick one:",
+        templates,
+    )
+    .with_help_message("esc to skip the template")
+    .prompt_skippable()
+    {
+        Ok(choice) => Ok(choice),
+        Err(inquire::InquireError::NotTTY) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Put the repository's template first so template-enforcing bots find their sections,
+/// followed by the bounty section
+#[must_use]
+pub fn merge(template: Option<&PrTemplate>, bounty_body: &str) -> String {
+    template.map_or_else(
+        || bounty_body.to_string(),
+        |template| format!("{}\n\n---\n\n{bounty_body}", template.body.trim_end()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_template_first() {
+        let template = PrTemplate {
+            path: ".github/pull_request_template.md".to_string(),
+            body: "## Description\n\n## Checklist\n- [ ] Tests\n\n".to_string(),
+        };
+
+        assert_eq!(
+            merge(Some(&template), "Resolves #1"),
+            "## Description\n\n## Checklist\n- [ ] Tests\n\n---\n\nResolves #1"
+        );
+        assert_eq!(merge(None, "Resolves #1"), "Resolves #1");
+    }
+
+    #[test]
+    fn test_names_match_in_any_case() {
+        assert!(is_template_file("PULL_REQUEST_TEMPLATE.md"));
+        assert!(is_template_file("pull_request_template.md"));
+        assert!(!is_template_file("pull_request_template.txt"));
+        assert!(is_contributing("CONTRIBUTING.md"));
+        assert!(is_contributing("Contributing.rst"));
+        assert!(is_contributing("contributing"));
+        assert!(!is_contributing("CONTRIBUTORS.md"));
+    }
+}
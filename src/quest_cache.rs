@@ -0,0 +1,86 @@
+use crate::QuestIssue;
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// How long cached quests are offered before completion refreshes them in the background
+const QUEST_CACHE_MAX_AGE: Duration = Duration::from_hours(1);
+
+/// Quests from the last fetch, so shell completion can offer them without network access
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct QuestCache {
+    /// Unix time of the last refresh
+    pub fetched_at: u64,
+    pub quests: Vec<CachedQuest>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CachedQuest {
+    pub issue_ref: String,
+    pub title: String,
+}
+
+impl QuestCache {
+    pub fn load() -> Result<Self> {
+        let path = quest_cache_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = quest_cache_path()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn from_quests(quests: &[QuestIssue]) -> Self {
+        Self {
+            fetched_at: now(),
+            quests: quests
+                .iter()
+                .map(|quest| CachedQuest {
+                    issue_ref: quest.repo_ref(),
+                    title: quest.title.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn is_stale(&self) -> bool {
+        now().saturating_sub(self.fetched_at) > QUEST_CACHE_MAX_AGE.as_secs()
+    }
+
+    /// Mark the cache fresh without new quests, so only one background refresh is started
+    pub fn touch(&mut self) {
+        self.fetched_at = now();
+    }
+}
+
+/// Cache freshly fetched quests; a failure only costs completions, so it's just logged
+///
+/// Only call this when a source answered, or an offline fetch empties a good cache.
+pub fn store(quests: &[QuestIssue]) {
+    if let Err(err) = QuestCache::from_quests(quests).save() {
+        debug!("failed to cache quests: {err:#}");
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+fn quest_cache_path() -> Result<PathBuf> {
+    crate::config::cache_file("quest_cache.json")
+}
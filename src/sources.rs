@@ -0,0 +1,363 @@
+use crate::config::{FeedConfig, SourcesConfig};
+use crate::{BountyBotClient, GitHub, QuestIssue, RepoIssue};
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Somewhere quests can be discovered
+#[cfg_attr(test, mockall::automock)]
+#[async_trait::async_trait]
+pub trait QuestSource: Send + Sync {
+    /// Human-readable name shown next to quests in the picker
+    fn name(&self) -> String;
+
+    /// Fetch the quests currently offered by this source
+    async fn fetch_quests(&self) -> Result<Vec<QuestIssue>>;
+}
+
+/// Open GitHub issues carrying bounty labels, in the repositories the user stars or watches
+pub struct GitHubLabelSource {
+    github: GitHub,
+    labels: Vec<String>,
+    followed_only: bool,
+}
+
+impl GitHubLabelSource {
+    #[must_use]
+    pub const fn new(github: GitHub, labels: Vec<String>, followed_only: bool) -> Self {
+        Self {
+            github,
+            labels,
+            followed_only,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl QuestSource for GitHubLabelSource {
+    fn name(&self) -> String {
+        "GitHub".to_string()
+    }
+
+    async fn fetch_quests(&self) -> Result<Vec<QuestIssue>> {
+        if !self.followed_only {
+            return self.github.search_labeled_issues(&self.labels, &[]).await;
+        }
+
+        let repos = followed_repos(&self.github).await?;
+        if repos.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.github
+            .search_labeled_issues(&self.labels, &repos)
+            .await
+    }
+}
+
+/// How long the starred and watched repositories are reused before being listed again
+const FOLLOWED_REPOS_MAX_AGE: Duration = Duration::from_hours(6);
+
+/// The repositories the user follows, so every poll doesn't page through them all again
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct FollowedRepos {
+    /// Unix time of the last listing
+    fetched_at: u64,
+    repos: Vec<String>,
+}
+
+impl FollowedRepos {
+    fn load() -> Result<Self> {
+        let path = crate::config::cache_file("followed_repos.json")?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = crate::config::cache_file("followed_repos.json")?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn is_fresh(&self, now: u64) -> bool {
+        now.saturating_sub(self.fetched_at) <= FOLLOWED_REPOS_MAX_AGE.as_secs()
+    }
+}
+
+/// The followed repositories, from the cache while it's fresh; cache errors are only logged
+async fn followed_repos(github: &GitHub) -> Result<Vec<String>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    match FollowedRepos::load() {
+        Ok(cached) if cached.is_fresh(now) => return Ok(cached.repos),
+        Ok(_) => {}
+        Err(err) => debug!("failed to read the followed repositories cache: {err:#}"),
+    }
+
+    let repos = github.followed_repos().await?;
+    let cache = FollowedRepos {
+        fetched_at: now,
+        repos,
+    };
+    if let Err(err) = cache.save() {
+        debug!("failed to cache the followed repositories: {err:#}");
+    }
+    Ok(cache.repos)
+}
+
+/// How long a feed gets to respond, so a hung platform can't stall `bounty watch`
+const FEED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A third-party bounty platform exposing its bounties as a public JSON feed
+pub struct JsonFeedSource {
+    client: reqwest::Client,
+    feed: FeedConfig,
+}
+
+impl JsonFeedSource {
+    #[must_use]
+    pub fn new(feed: FeedConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(FEED_TIMEOUT)
+                .build()
+                .expect("failed to build the HTTP client"),
+            feed,
+        }
+    }
+
+    /// Turn a feed document into quests, skipping entries that don't point at a GitHub issue
+    fn parse_feed(&self, document: &serde_json::Value) -> Result<Vec<QuestIssue>> {
+        let items = document
+            .pointer(&self.feed.items)
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| eyre::eyre!("no array at `{}` in feed", self.feed.items))?;
+
+        Ok(items
+            .iter()
+            .filter_map(|item| {
+                let url = item.pointer(&self.feed.url_field)?.as_str()?;
+                let title = item.pointer(&self.feed.title_field)?.as_str()?;
+                Some(QuestIssue {
+                    title: title.to_string(),
+                    issue: RepoIssue::parse(url).ok()?,
+                    source: self.feed.name.clone(),
+                })
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl QuestSource for JsonFeedSource {
+    fn name(&self) -> String {
+        self.feed.name.clone()
+    }
+
+    async fn fetch_quests(&self) -> Result<Vec<QuestIssue>> {
+        let document = self
+            .client
+            .get(&self.feed.url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await
+            .wrap_err_with(|| format!("failed to read feed from {}", self.feed.url))?;
+
+        self.parse_feed(&document)
+    }
+}
+
+/// Build the quest sources enabled in the config
+///
+/// GitHub label search is only available when a GitHub client is provided.
+#[must_use]
+pub fn configured_sources(
+    config: &SourcesConfig,
+    github: Option<GitHub>,
+) -> Vec<Box<dyn QuestSource>> {
+    let mut sources: Vec<Box<dyn QuestSource>> = Vec::new();
+
+    if config.bountybot {
+        sources.push(Box::new(BountyBotClient::new()));
+    }
+
+    match github {
+        Some(github) if !config.github_labels.is_empty() => {
+            sources.push(Box::new(GitHubLabelSource::new(
+                github,
+                config.github_labels.clone(),
+                config.github_followed_only,
+            )));
+        }
+        None if !config.github_labels.is_empty() => {
+            warn!("no GitHub token available, skipping GitHub label search");
+        }
+        _ => {}
+    }
+
+    sources.extend(
+        config
+            .feeds
+            .iter()
+            .cloned()
+            .map(|feed| Box::new(JsonFeedSource::new(feed)) as Box<dyn QuestSource>),
+    );
+
+    sources
+}
+
+/// Quests fetched from every source, and the names of the sources that answered or failed
+#[derive(Debug, Default)]
+pub struct Fetched {
+    pub quests: Vec<QuestIssue>,
+    pub answered: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl Fetched {
+    /// Whether no source answered, so `quests` says nothing about which quests exist
+    #[must_use]
+    pub fn all_failed(&self) -> bool {
+        self.answered.is_empty() && !self.failed.is_empty()
+    }
+}
+
+/// Fetch quests from every source, de-duplicated by issue
+///
+/// Sources that fail are skipped with a warning so one broken platform doesn't hide the rest.
+/// When the same issue is offered by several sources, the first source wins.
+pub async fn fetch_all(sources: &[Box<dyn QuestSource>]) -> Fetched {
+    let mut seen = HashSet::new();
+    let mut fetched_all = Fetched::default();
+
+    for source in sources {
+        let fetched = match source.fetch_quests().await {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                warn!(source = %source.name(), "failed to fetch quests: {e:#}");
+                fetched_all.failed.push(source.name());
+                continue;
+            }
+        };
+
+        debug!(source = %source.name(), count = fetched.len(), "fetched quests");
+        fetched_all.answered.push(source.name());
+        fetched_all.quests.extend(
+            fetched
+                .into_iter()
+                .filter(|quest| seen.insert(quest.issue.clone())),
+        );
+    }
+
+    fetched_all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quest(issue_number: u64, source: &str) -> QuestIssue {
+        QuestIssue {
+            title: format!("Issue {issue_number}"),
+            issue: RepoIssue {
+                owner: "ghbountybot".to_string(),
+                repo: "cli".to_string(),
+                issue_number,
+            },
+            source: source.to_string(),
+        }
+    }
+
+    fn mock_source(name: &'static str, quests: Vec<QuestIssue>) -> Box<dyn QuestSource> {
+        let mut source = MockQuestSource::new();
+        source.expect_name().return_const(name.to_string());
+        source.expect_fetch_quests().return_once(move || Ok(quests));
+        Box::new(source)
+    }
+
+    #[test]
+    fn test_followed_repos_expire() {
+        let cached = FollowedRepos {
+            fetched_at: 1_000,
+            repos: vec!["ghbountybot/cli".to_string()],
+        };
+
+        assert!(cached.is_fresh(1_000 + FOLLOWED_REPOS_MAX_AGE.as_secs()));
+        assert!(!cached.is_fresh(1_001 + FOLLOWED_REPOS_MAX_AGE.as_secs()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_deduplicates_by_issue() {
+        let sources = vec![
+            mock_source("first", vec![quest(1, "first"), quest(2, "first")]),
+            mock_source("second", vec![quest(2, "second"), quest(3, "second")]),
+        ];
+
+        let fetched = fetch_all(&sources).await;
+
+        let refs: Vec<_> = fetched
+            .quests
+            .iter()
+            .map(|quest| (quest.issue.issue_number, quest.source.as_str()))
+            .collect();
+        assert_eq!(refs, [(1, "first"), (2, "first"), (3, "second")]);
+    }
+
+    fn failing_source() -> Box<dyn QuestSource> {
+        let mut failing = MockQuestSource::new();
+        failing.expect_name().return_const("broken".to_string());
+        failing
+            .expect_fetch_quests()
+            .return_once(|| Err(eyre::eyre!("offline")));
+        Box::new(failing)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_skips_failing_sources() {
+        let sources = vec![failing_source(), mock_source("ok", vec![quest(7, "ok")])];
+
+        let fetched = fetch_all(&sources).await;
+        assert_eq!(fetched.quests.len(), 1);
+        assert_eq!(fetched.quests[0].issue.issue_number, 7);
+        assert!(!fetched.all_failed());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_reports_when_every_source_failed() {
+        let fetched = fetch_all(&[failing_source(), failing_source()]).await;
+        assert!(fetched.quests.is_empty());
+        assert!(fetched.all_failed());
+    }
+
+    #[test]
+    fn test_parse_feed() {
+        let source = JsonFeedSource::new(FeedConfig {
+            name: "feed".to_string(),
+            url: "https://example.com/bounties.json".to_string(),
+            items: "/bounties".to_string(),
+            url_field: "/issue/url".to_string(),
+            title_field: "/title".to_string(),
+        });
+        let document = serde_json::json!({
+            "bounties": [
+                { "title": "Fix it", "issue": { "url": "https://github.com/ghbountybot/cli/issues/2" } },
+                { "title": "Not GitHub", "issue": { "url": "https://example.com/task/9" } },
+            ]
+        });
+
+        let quests = source.parse_feed(&document).unwrap();
+        assert_eq!(quests.len(), 1);
+        assert_eq!(quests[0].repo_ref(), "ghbountybot/cli#2");
+        assert_eq!(quests[0].source, "feed");
+    }
+}
@@ -0,0 +1,265 @@
+use crate::RepoIssue;
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A bounty started with `bounty solve`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrackedBounty {
+    pub owner: String,
+    pub repo: String,
+    pub issue_number: u64,
+    pub fork_owner: String,
+    pub branch: String,
+    /// Upstream branch the pull request targets; empty for bounties started by older
+    /// versions, meaning the repository's default branch
+    #[serde(default)]
+    pub base: String,
+    /// Local checkout of the fork
+    pub checkout: Option<PathBuf>,
+    pub pr_number: Option<u64>,
+    pub pr_url: Option<String>,
+    /// The claim comment posted on the upstream issue, so it can be removed on abandon
+    pub claim_comment_id: Option<u64>,
+    /// Whether we assigned ourselves to the upstream issue
+    #[serde(default)]
+    pub self_assigned: bool,
+    /// Whether the pre-push hook should open the draft PR
+    #[serde(default)]
+    pub pr_on_push: bool,
+    /// Post-checkout hooks that failed, so they can be rerun by hand
+    #[serde(default)]
+    pub failed_hooks: Vec<String>,
+    /// Pull request state as of the last refresh, for `bounty prompt`
+    #[serde(default)]
+    pub pr_state: Option<PrState>,
+    /// CI status of the pull request as of the last refresh
+    #[serde(default)]
+    pub ci: Option<CiStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrState {
+    Draft,
+    Ready,
+    Merged,
+    Closed,
+}
+
+impl std::fmt::Display for PrState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Draft => "draft",
+            Self::Ready => "ready",
+            Self::Merged => "merged",
+            Self::Closed => "closed",
+        })
+    }
+}
+
+/// Overall result of a pull request's check runs, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CiStatus {
+    Passing,
+    Pending,
+    Failing,
+}
+
+impl CiStatus {
+    /// Combine check run conclusions (`None` while a run is in progress) into the worst status
+    pub fn summarize<'a>(conclusions: impl IntoIterator<Item = Option<&'a str>>) -> Option<Self> {
+        conclusions
+            .into_iter()
+            .map(|conclusion| match conclusion {
+                None => Self::Pending,
+                Some("success" | "neutral" | "skipped") => Self::Passing,
+                Some(_) => Self::Failing,
+            })
+            .max()
+    }
+
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Passing => "✓",
+            Self::Pending => "…",
+            Self::Failing => "✗",
+        }
+    }
+}
+
+impl TrackedBounty {
+    #[must_use]
+    pub fn repo_issue(&self) -> RepoIssue {
+        RepoIssue {
+            owner: self.owner.clone(),
+            repo: self.repo.clone(),
+            issue_number: self.issue_number,
+        }
+    }
+}
+
+/// Bounties being worked on, persisted between runs
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct State {
+    pub bounties: Vec<TrackedBounty>,
+}
+
+impl State {
+    pub fn load() -> Result<Self> {
+        let path = state_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = state_path()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Find the tracked bounty for an issue
+    #[must_use]
+    pub fn find(&self, issue: &RepoIssue) -> Option<&TrackedBounty> {
+        self.bounties
+            .iter()
+            .find(|bounty| bounty.repo_issue() == *issue)
+    }
+
+    /// Find the tracked bounty whose checkout contains `path`
+    ///
+    /// Bounties started in a reused clone share its checkout, so then it's the one whose branch
+    /// is `head_branch`, the branch checked out there.
+    pub fn find_by_path(
+        &self,
+        path: &Path,
+        head_branch: Option<&str>,
+    ) -> Result<Option<&TrackedBounty>> {
+        let in_checkout: Vec<_> = self
+            .bounties
+            .iter()
+            .filter(|bounty| {
+                bounty
+                    .checkout
+                    .as_deref()
+                    .is_some_and(|checkout| path.starts_with(checkout))
+            })
+            .collect();
+        if in_checkout.len() < 2 {
+            return Ok(in_checkout.first().copied());
+        }
+
+        let mut on_branch = in_checkout
+            .into_iter()
+            .filter(|bounty| Some(bounty.branch.as_str()) == head_branch);
+        match (on_branch.next(), on_branch.next()) {
+            (Some(bounty), None) => Ok(Some(bounty)),
+            _ => bail!("several bounties share this checkout; pass the issue reference"),
+        }
+    }
+
+    /// Track a bounty, replacing any previous record for the same issue
+    pub fn upsert(&mut self, bounty: TrackedBounty) {
+        self.remove(&bounty.repo_issue());
+        self.bounties.push(bounty);
+    }
+
+    /// Stop tracking a bounty, returning its record
+    pub fn remove(&mut self, issue: &RepoIssue) -> Option<TrackedBounty> {
+        let index = self
+            .bounties
+            .iter()
+            .position(|bounty| bounty.repo_issue() == *issue)?;
+        Some(self.bounties.remove(index))
+    }
+}
+
+fn state_path() -> Result<PathBuf> {
+    crate::config::state_file("bounties.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ci_status_is_the_worst_conclusion() {
+        assert_eq!(CiStatus::summarize([]), None);
+        assert_eq!(
+            CiStatus::summarize([Some("success"), Some("skipped")]),
+            Some(CiStatus::Passing)
+        );
+        assert_eq!(
+            CiStatus::summarize([Some("success"), None]),
+            Some(CiStatus::Pending)
+        );
+        assert_eq!(
+            CiStatus::summarize([None, Some("failure"), Some("success")]),
+            Some(CiStatus::Failing)
+        );
+    }
+
+    #[test]
+    fn test_load_bounty_without_base() {
+        let state: State = serde_json::from_str(
+            r#"{"bounties": [{
+                "owner": "acme", "repo": "rocket", "issue_number": 12,
+                "fork_owner": "octocat", "branch": "issue-12", "checkout": null,
+                "pr_number": null, "pr_url": null, "claim_comment_id": null
+            }]}"#,
+        )
+        .unwrap();
+        assert_eq!(state.bounties[0].base, "");
+    }
+
+    fn bounty(issue_number: u64, branch: &str, checkout: &str) -> TrackedBounty {
+        TrackedBounty {
+            owner: "acme".to_string(),
+            repo: "rocket".to_string(),
+            issue_number,
+            fork_owner: "octocat".to_string(),
+            branch: branch.to_string(),
+            base: String::new(),
+            checkout: Some(PathBuf::from(checkout)),
+            pr_number: None,
+            pr_url: None,
+            claim_comment_id: None,
+            self_assigned: false,
+            pr_on_push: false,
+            failed_hooks: Vec::new(),
+            pr_state: None,
+            ci: None,
+        }
+    }
+
+    #[test]
+    fn test_find_by_path_prefers_checked_out_branch() {
+        let state = State {
+            bounties: vec![
+                bounty(1, "issue-1", "/work/rocket"),
+                bounty(2, "issue-2", "/work/rocket"),
+                bounty(3, "issue-3", "/work/other"),
+            ],
+        };
+        let path = Path::new("/work/rocket/src");
+
+        let found = state.find_by_path(path, Some("issue-2")).unwrap();
+        assert_eq!(found.unwrap().issue_number, 2);
+        let found = state.find_by_path(Path::new("/work/other"), None).unwrap();
+        assert_eq!(found.unwrap().issue_number, 3);
+        assert!(state
+            .find_by_path(Path::new("/work/new"), None)
+            .unwrap()
+            .is_none());
+        assert!(state.find_by_path(path, Some("main")).is_err());
+        assert!(state.find_by_path(path, None).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::config::TemplatesConfig;
+use regex::{Captures, Regex};
+use std::sync::LazyLock;
+
+const DEFAULT_BRANCH: &str = "issue-{issue_number}";
+const DEFAULT_COMMIT_MESSAGE: &str = "Start work on bounty";
+const DEFAULT_PR_TITLE: &str = "Resolves: #{issue_number}";
+const DEFAULT_PR_BODY: &str = "This pull request resolves issue #{issue_number}.\n\n\
+    Work in progress 🚧\n\n\
+    ## Bounty Guidelines:\n\
+    - Please post your progress at least once per day\n\
+    - This PR will remain in draft status until work is complete\n\
+    - Only mark as ready for review when you have finished your work\n\
+    - You may be unassigned from this issue if there are no pushes for several days\n\
+    \n\
+    ## Community\n\
+    [Join Our Discord](https://discord.gg/jcQpX6jW8u) to connect with other bounty hunters and give feedback.\n\
+    \n\
+    Happy coding! 🎉";
+
+/// Fully resolved templates for what `bounty solve` creates
+///
+/// Each template may use these placeholders: `{owner}`, `{repo}`, `{issue_number}`,
+/// `{issue_title}`, `{slug}` (the slugified issue title), `{labels}` (comma separated)
+/// and `{author}` (who opened the issue).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Templates {
+    pub branch: String,
+    pub commit_message: String,
+    pub pr_title: String,
+    pub pr_body: String,
+    pub use_repo_pr_template: bool,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self {
+            branch: DEFAULT_BRANCH.to_string(),
+            commit_message: DEFAULT_COMMIT_MESSAGE.to_string(),
+            pr_title: DEFAULT_PR_TITLE.to_string(),
+            pr_body: DEFAULT_PR_BODY.to_string(),
+            use_repo_pr_template: true,
+        }
+    }
+}
+
+impl Templates {
+    /// Resolve configured templates, using the defaults for anything unset
+    #[must_use]
+    pub fn from_config(config: TemplatesConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            branch: config.branch.unwrap_or(defaults.branch),
+            commit_message: config.commit_message.unwrap_or(defaults.commit_message),
+            pr_title: config.pr_title.unwrap_or(defaults.pr_title),
+            pr_body: config.pr_body.unwrap_or(defaults.pr_body),
+            use_repo_pr_template: config
+                .use_repo_pr_template
+                .unwrap_or(defaults.use_repo_pr_template),
+        }
+    }
+
+    /// Render every template for an issue
+    #[must_use]
+    pub fn render(&self, vars: &IssueVars) -> Self {
+        let vars = vars.as_pairs();
+        Self {
+            branch: render(&self.branch, &vars),
+            commit_message: render(&self.commit_message, &vars),
+            pr_title: render(&self.pr_title, &vars),
+            pr_body: render(&self.pr_body, &vars),
+            use_repo_pr_template: self.use_repo_pr_template,
+        }
+    }
+}
+
+/// Values available to templates for a single issue
+#[derive(Debug, Clone, Default)]
+pub struct IssueVars {
+    pub owner: String,
+    pub repo: String,
+    pub issue_number: u64,
+    pub issue_title: String,
+    pub labels: Vec<String>,
+    pub author: String,
+}
+
+impl IssueVars {
+    #[must_use]
+    pub fn from_issue(owner: &str, repo: &str, issue: &octocrab::models::issues::Issue) -> Self {
+        Self {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            issue_number: issue.number,
+            issue_title: issue.title.clone(),
+            labels: issue
+                .labels
+                .iter()
+                .map(|label| label.name.clone())
+                .collect(),
+            author: issue.user.login.clone(),
+        }
+    }
+
+    fn as_pairs(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("owner", self.owner.clone()),
+            ("repo", self.repo.clone()),
+            ("issue_number", self.issue_number.to_string()),
+            ("issue_title", self.issue_title.clone()),
+            ("slug", slugify(&self.issue_title)),
+            ("labels", self.labels.join(", ")),
+            ("author", self.author.clone()),
+        ]
+    }
+}
+
+static PLACEHOLDER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\w+)\}").unwrap());
+
+/// Replace `{name}` placeholders in a template with the given values
+///
+/// Placeholders without a value are left untouched so typos stay visible. Values are inserted
+/// as they are, so an issue title containing `{author}` isn't expanded again.
+#[must_use]
+pub fn render(template: &str, vars: &[(&str, impl AsRef<str>)]) -> String {
+    PLACEHOLDER
+        .replace_all(template, |captures: &Captures<'_>| {
+            vars.iter()
+                .find(|(name, _)| *name == &captures[1])
+                .map_or_else(
+                    || captures[0].to_string(),
+                    |(_, value)| value.as_ref().to_string(),
+                )
+        })
+        .into_owned()
+}
+
+/// Longest slug produced by [`slugify`], so branch names stay readable
+const MAX_SLUG_LEN: usize = 40;
+
+/// Turn free text such as an issue title into a lowercase, dash-separated slug
+///
+/// `Crash on startup (macOS)!` becomes `crash-on-startup-macos`.
+#[must_use]
+pub fn slugify(text: &str) -> String {
+    let mut words = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase);
+
+    let Some(first) = words.next() else {
+        return String::new();
+    };
+    let mut slug: String = first.chars().take(MAX_SLUG_LEN).collect();
+
+    for word in words {
+        if slug.len() + 1 + word.len() > MAX_SLUG_LEN {
+            break;
+        }
+        slug.push('-');
+        slug.push_str(&word);
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates_render_issue_vars() {
+        let templates = Templates {
+            branch: "fix/{issue_number}-{slug}".to_string(),
+            ..Templates::default()
+        };
+        let vars = IssueVars {
+            issue_number: 1234,
+            issue_title: "Crash on startup".to_string(),
+            ..IssueVars::default()
+        };
+
+        let rendered = templates.render(&vars);
+        assert_eq!(rendered.branch, "fix/1234-crash-on-startup");
+        assert_eq!(rendered.pr_title, "Resolves: #1234");
+    }
+
+    #[test]
+    fn test_render_does_not_expand_placeholders_in_values() {
+        let templates = Templates {
+            commit_message: "{issue_title} by {author}".to_string(),
+            ..Templates::default()
+        };
+        let vars = IssueVars {
+            issue_number: 7,
+            issue_title: "Show {author} and #{issue_number} literally".to_string(),
+            author: "octocat".to_string(),
+            ..IssueVars::default()
+        };
+
+        assert_eq!(
+            templates.render(&vars).commit_message,
+            "Show {author} and #{issue_number} literally by octocat"
+        );
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Crash on startup (macOS)!"),
+            "crash-on-startup-macos"
+        );
+        assert_eq!(slugify("  --Fix  `foo::bar`--  "), "fix-foo-bar");
+        assert_eq!(slugify("日本語"), "");
+
+        let long = slugify("a very long issue title that keeps going well past the limit");
+        assert!(long.len() <= MAX_SLUG_LEN);
+        assert_eq!(long, "a-very-long-issue-title-that-keeps-going");
+    }
+
+    #[test]
+    fn test_render_replaces_known_placeholders() {
+        let rendered = render(
+            "/attempt #{issue_number} in {pr_url} {unknown}",
+            &[("issue_number", "42"), ("pr_url", "https://example.com")],
+        );
+        assert_eq!(rendered, "/attempt #42 in https://example.com {unknown}");
+    }
+}
@@ -0,0 +1,40 @@
+//! Changing environment variables from tests that run in parallel
+
+use std::ffi::{OsStr, OsString};
+use std::sync::{Mutex, MutexGuard};
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// An environment variable set for as long as this lives, then restored
+///
+/// Holds a lock shared by every test that changes the environment, so they run one at a time.
+pub struct EnvVar {
+    name: &'static str,
+    previous: Option<OsString>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl EnvVar {
+    pub fn set(name: &'static str, value: impl AsRef<OsStr>) -> Self {
+        // A test that panicked while holding the lock has still restored its variable
+        let lock = LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let previous = std::env::var_os(name);
+        std::env::set_var(name, value);
+        Self {
+            name,
+            previous,
+            _lock: lock,
+        }
+    }
+}
+
+impl Drop for EnvVar {
+    fn drop(&mut self) {
+        match &self.previous {
+            Some(value) => std::env::set_var(self.name, value),
+            None => std::env::remove_var(self.name),
+        }
+    }
+}
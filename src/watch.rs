@@ -0,0 +1,244 @@
+use crate::sources::Fetched;
+use crate::QuestIssue;
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long a quest no source offers any more is remembered
+const FORGET_AFTER: Duration = Duration::from_hours(30 * 24);
+
+/// A saved filter deciding which new quests are worth a notification
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct QuestFilter {
+    /// Only match quests in this organization (`owner`) or repository (`owner/repo`)
+    pub repo: Option<String>,
+    /// Only match quests whose title contains any of these words (case-insensitive)
+    pub keywords: Vec<String>,
+}
+
+impl QuestFilter {
+    /// Check whether a quest satisfies every constraint of this filter
+    #[must_use]
+    pub fn matches(&self, quest: &QuestIssue) -> bool {
+        let repo_matches = self
+            .repo
+            .as_deref()
+            .is_none_or(|repo| matches_repo(repo, quest));
+
+        let title = quest.title.to_lowercase();
+        let keywords_match = self.keywords.is_empty()
+            || self
+                .keywords
+                .iter()
+                .any(|keyword| title.contains(&keyword.to_lowercase()));
+
+        repo_matches && keywords_match
+    }
+}
+
+fn matches_repo(repo: &str, quest: &QuestIssue) -> bool {
+    let (owner, name) = repo.split_once('/').unwrap_or((repo, ""));
+    quest.issue.owner.eq_ignore_ascii_case(owner)
+        && (name.is_empty() || quest.issue.repo.eq_ignore_ascii_case(name))
+}
+
+/// Check a quest against a set of filters, where no filters means everything matches
+#[must_use]
+pub fn matches_any(filters: &[QuestFilter], quest: &QuestIssue) -> bool {
+    filters.is_empty() || filters.iter().any(|filter| filter.matches(quest))
+}
+
+/// Quests already seen by `bounty watch`, persisted between runs
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SeenQuests {
+    /// By issue reference, e.g. `owner/repo#123`
+    quests: BTreeMap<String, SeenQuest>,
+    /// Sources whose existing quests have been recorded, so new ones from them are notified
+    seeded: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct SeenQuest {
+    source: String,
+    /// Unix time of the last poll that offered it
+    last_seen: u64,
+}
+
+/// What a poll changed
+#[derive(Debug, Default)]
+pub struct Observed {
+    /// Quests from seeded sources that hadn't been seen before
+    pub new: Vec<QuestIssue>,
+    /// Existing quests recorded from sources answering for the first time
+    pub seeded: usize,
+}
+
+impl SeenQuests {
+    pub fn load() -> Result<Self> {
+        let path = seen_quests_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = seen_quests_path()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record a poll's quests at unix time `now`
+    ///
+    /// The first time a source answers, its quests are only recorded instead of flooding
+    /// notifications; a source that failed is seeded once it answers. Quests no source has
+    /// offered for [`FORGET_AFTER`] are forgotten, unless their source failed this time.
+    pub fn observe(&mut self, fetched: Fetched, now: u64) -> Observed {
+        let unseen: Vec<QuestIssue> = fetched
+            .quests
+            .into_iter()
+            .filter(|quest| {
+                let entry = SeenQuest {
+                    source: quest.source.clone(),
+                    last_seen: now,
+                };
+                self.quests.insert(quest.repo_ref(), entry).is_none()
+            })
+            .collect();
+        let (new, seeded): (Vec<_>, Vec<_>) = unseen
+            .into_iter()
+            .partition(|quest| self.seeded.contains(&quest.source));
+        self.seeded.extend(fetched.answered);
+
+        let cutoff = now.saturating_sub(FORGET_AFTER.as_secs());
+        self.quests
+            .retain(|_, quest| quest.last_seen >= cutoff || fetched.failed.contains(&quest.source));
+        Observed {
+            new,
+            seeded: seeded.len(),
+        }
+    }
+}
+
+fn seen_quests_path() -> Result<PathBuf> {
+    crate::config::state_file("seen_quests.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RepoIssue;
+
+    fn quest(owner: &str, repo: &str, issue_number: u64, title: &str) -> QuestIssue {
+        QuestIssue {
+            title: title.to_string(),
+            issue: RepoIssue {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                issue_number,
+            },
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_filter_by_owner_and_repo() {
+        let react = quest("facebook", "react", 42, "Fix hydration crash");
+        let org = QuestFilter {
+            repo: Some("Facebook".to_string()),
+            ..QuestFilter::default()
+        };
+        let other = QuestFilter {
+            repo: Some("facebook/jest".to_string()),
+            ..QuestFilter::default()
+        };
+
+        assert!(org.matches(&react));
+        assert!(!other.matches(&react));
+    }
+
+    #[test]
+    fn test_filter_by_keywords() {
+        let react = quest("facebook", "react", 42, "Fix hydration crash");
+        let filter = QuestFilter {
+            keywords: vec!["CRASH".to_string(), "docs".to_string()],
+            ..QuestFilter::default()
+        };
+
+        assert!(filter.matches(&react));
+        assert!(!filter.matches(&quest("facebook", "react", 43, "Improve typings")));
+        assert!(matches_any(&[], &react));
+    }
+
+    fn fetched(quests: Vec<QuestIssue>, failed: &[&str]) -> Fetched {
+        let failed: Vec<String> = failed.iter().map(ToString::to_string).collect();
+        Fetched {
+            answered: if failed.is_empty() {
+                vec!["test".to_string()]
+            } else {
+                Vec::new()
+            },
+            failed,
+            quests,
+        }
+    }
+
+    #[test]
+    fn test_observe_returns_only_new_quests() {
+        let mut seen = SeenQuests::default();
+        let first = seen.observe(
+            fetched(
+                vec![quest("a", "b", 1, "one"), quest("a", "b", 2, "two")],
+                &[],
+            ),
+            0,
+        );
+        assert!(first.new.is_empty());
+        assert_eq!(first.seeded, 2);
+
+        let second = seen.observe(
+            fetched(
+                vec![quest("a", "b", 2, "two"), quest("a", "b", 3, "three")],
+                &[],
+            ),
+            1,
+        );
+        assert_eq!(second.new.len(), 1);
+        assert_eq!(second.new[0].issue.issue_number, 3);
+    }
+
+    #[test]
+    fn test_failed_source_is_seeded_when_it_answers() {
+        let mut seen = SeenQuests::default();
+        let failed = Fetched {
+            answered: vec!["other".to_string()],
+            failed: vec!["test".to_string()],
+            quests: Vec::new(),
+        };
+        seen.observe(failed, 0);
+
+        let recovered = seen.observe(fetched(vec![quest("a", "b", 1, "one")], &[]), 1);
+        assert!(recovered.new.is_empty());
+        assert_eq!(recovered.seeded, 1);
+    }
+
+    #[test]
+    fn test_observe_forgets_quests_gone_for_long() {
+        let mut seen = SeenQuests::default();
+        let later = FORGET_AFTER.as_secs() + 1;
+        seen.observe(fetched(vec![quest("a", "b", 1, "one")], &[]), 0);
+
+        seen.observe(fetched(Vec::new(), &["test"]), later);
+        assert_eq!(seen.quests.len(), 1);
+
+        seen.observe(fetched(Vec::new(), &[]), later);
+        assert!(seen.quests.is_empty());
+    }
+}